@@ -0,0 +1,70 @@
+use crate::player::Player;
+
+/// Cámara con plano de proyección: un vector de dirección unitario y un plano
+/// perpendicular cuya mitad mide `tan(fov / 2)`. Las paredes y el piso la
+/// comparten para que ambos proyecten igual.
+///
+/// Mirar arriba o abajo no rota la cámara: corre el horizonte en la pantalla
+/// (y-shearing), así las paredes siguen verticales.
+pub struct Camera {
+    pub x: f64,
+    pub y: f64,
+    pub dir_x: f64,
+    pub dir_y: f64,
+    pub plane_x: f64,
+    pub plane_y: f64,
+    pub pitch: f64,
+    /// Altura de los ojos sobre el piso, en unidades de pared
+    pub eye_height: f64,
+}
+
+impl Camera {
+    pub fn from_player(player: &Player) -> Self {
+        let (sin, cos) = player.direction.sin_cos();
+        let plane_length = (player.fov / 2.0).tan();
+
+        Self {
+            x: player.x,
+            y: player.y,
+            dir_x: cos,
+            dir_y: sin,
+            plane_x: -sin * plane_length,
+            plane_y: cos * plane_length,
+            pitch: player.pitch,
+            eye_height: player.eye_height,
+        }
+    }
+
+    /// Fila de pantalla del horizonte para una pantalla de `screen_height` de
+    /// alto. Una unidad de altura a distancia `d` mide `screen_height / d`
+    /// píxeles, así que la inclinación corre el horizonte `tan(pitch)` veces eso.
+    pub fn horizon(&self, screen_height: usize) -> f64 {
+        screen_height as f64 / 2.0 + self.pitch.tan() * screen_height as f64
+    }
+
+    /// Dirección del rayo para `camera_x` en [-1, 1] (izquierda a derecha de la pantalla).
+    /// No está normalizada: avanzar `t` veces este vector recorre una distancia
+    /// perpendicular `t` respecto al plano de la cámara.
+    pub fn ray_direction(&self, camera_x: f64) -> (f64, f64) {
+        (
+            self.dir_x + self.plane_x * camera_x,
+            self.dir_y + self.plane_y * camera_x,
+        )
+    }
+
+    /// Para una fila del piso o del techo que está a `distance` de la cámara:
+    /// el punto del mundo que ve la columna 0 y cuánto se avanza por columna
+    /// en una pantalla de `columns` de ancho. Coincide con los rayos de
+    /// `ray_direction`, así que el piso queda pegado a las paredes.
+    pub fn floor_row(&self, distance: f64, columns: usize) -> ((f64, f64), (f64, f64)) {
+        let (left_x, left_y) = self.ray_direction(-1.0);
+        let (right_x, right_y) = self.ray_direction(1.0);
+
+        let start = (self.x + distance * left_x, self.y + distance * left_y);
+        let step = (
+            distance * (right_x - left_x) / columns as f64,
+            distance * (right_y - left_y) / columns as f64,
+        );
+        (start, step)
+    }
+}
@@ -0,0 +1,221 @@
+use crate::map::Map;
+
+// Correcciones por paso; en una esquina interior hacen falta dos
+const RESOLVE_PASSES: usize = 4;
+// Radio mínimo: con radio cero los pasos de medio radio serían infinitos
+const MIN_RADIUS: f64 = 0.01;
+// Tope de pasos por movimiento, para que un desplazamiento enorme no congele el juego
+const MAX_STEPS: usize = 256;
+
+/// Mueve un círculo de radio `radius` por `(dx, dy)` contra la rejilla del mapa.
+/// El desplazamiento se parte en pasos de medio radio para no atravesar paredes
+/// delgadas; en cada paso la penetración se corrige empujando al círculo a lo
+/// largo de la normal de la pared, así que el movimiento tangencial se conserva
+/// y el jugador se desliza por las paredes.
+pub fn move_circle(map: &Map, x: f64, y: f64, dx: f64, dy: f64, radius: f64) -> (f64, f64) {
+    let radius = radius.max(MIN_RADIUS);
+    let length = (dx * dx + dy * dy).sqrt();
+    let steps = ((length / (radius * 0.5)).ceil().max(1.0) as usize).min(MAX_STEPS);
+
+    let (mut x, mut y) = (x, y);
+    for _ in 0..steps {
+        let (nx, ny) = resolve_circle(map, x + dx / steps as f64, y + dy / steps as f64, radius);
+        x = nx;
+        y = ny;
+    }
+    (x, y)
+}
+
+/// Saca al círculo de todas las celdas sólidas (paredes y puertas cerradas) que
+/// lo tocan. En cada pasada se corrige solo la penetración más profunda: así,
+/// junto a una pared formada por varias celdas, manda la cara de la pared y no
+/// la esquina de la celda vecina.
+pub fn resolve_circle(map: &Map, x: f64, y: f64, radius: f64) -> (f64, f64) {
+    let radius = radius.max(MIN_RADIUS);
+    let (mut x, mut y) = (x, y);
+
+    for _ in 0..RESOLVE_PASSES {
+        let min_x = (x - radius).floor() as i32;
+        let max_x = (x + radius).floor() as i32;
+        let min_y = (y - radius).floor() as i32;
+        let max_y = (y + radius).floor() as i32;
+
+        let mut deepest: Option<(f64, f64)> = None;
+        for cell_y in min_y..=max_y {
+            for cell_x in min_x..=max_x {
+                if !map.is_solid_cell(cell_x, cell_y) {
+                    continue;
+                }
+
+                if let Some(push) = circle_cell_push(x, y, radius, cell_x, cell_y) {
+                    let depth = push.0 * push.0 + push.1 * push.1;
+                    if deepest.is_none_or(|(dx, dy)| depth > dx * dx + dy * dy) {
+                        deepest = Some(push);
+                    }
+                }
+            }
+        }
+
+        match deepest {
+            Some((push_x, push_y)) => {
+                x += push_x;
+                y += push_y;
+            }
+            None => break,
+        }
+    }
+
+    (x, y)
+}
+
+/// Desplazamiento mínimo que separa al círculo de la celda, o `None` si no se tocan.
+fn circle_cell_push(x: f64, y: f64, radius: f64, cell_x: i32, cell_y: i32) -> Option<(f64, f64)> {
+    let left = cell_x as f64;
+    let top = cell_y as f64;
+
+    // Punto de la celda más cercano al centro del círculo
+    let closest_x = x.clamp(left, left + 1.0);
+    let closest_y = y.clamp(top, top + 1.0);
+    let dx = x - closest_x;
+    let dy = y - closest_y;
+    let distance_squared = dx * dx + dy * dy;
+
+    if distance_squared >= radius * radius {
+        return None;
+    }
+
+    if distance_squared > 1e-12 {
+        let distance = distance_squared.sqrt();
+        let depth = radius - distance;
+        return Some((dx / distance * depth, dy / distance * depth));
+    }
+
+    // El centro quedó dentro de la celda: salir por el lado más cercano
+    let exits = [
+        (left - radius - x, 0.0),
+        (left + 1.0 + radius - x, 0.0),
+        (0.0, top - radius - y),
+        (0.0, top + 1.0 + radius - y),
+    ];
+    exits
+        .into_iter()
+        .min_by(|a, b| (a.0.abs() + a.1.abs()).total_cmp(&(b.0.abs() + b.1.abs())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f64 = 0.25;
+
+    fn map(rows: &[&str]) -> Map {
+        let grid = rows.iter().map(|row| row.chars().collect()).collect();
+        Map::from_grid(String::from("prueba"), grid, (1.5, 1.5), 0.0, (1.0, 1.0))
+    }
+
+    // El círculo no se mete en ninguna celda sólida (con un margen por redondeo)
+    fn assert_clear(map: &Map, x: f64, y: f64) {
+        for cell_y in 0..map.height as i32 {
+            for cell_x in 0..map.width as i32 {
+                if !map.is_solid_cell(cell_x, cell_y) {
+                    continue;
+                }
+                let dx = x - x.clamp(cell_x as f64, cell_x as f64 + 1.0);
+                let dy = y - y.clamp(cell_y as f64, cell_y as f64 + 1.0);
+                let distance = (dx * dx + dy * dy).sqrt();
+                assert!(
+                    distance >= RADIUS - 1e-9,
+                    "({}, {}) se mete {} en la celda ({}, {})",
+                    x,
+                    y,
+                    RADIUS - distance,
+                    cell_x,
+                    cell_y
+                );
+            }
+        }
+    }
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "se esperaba {}, se obtuvo {}", expected, actual);
+    }
+
+    #[test]
+    fn slides_along_straight_wall() {
+        let map = map(&["#######", "#     #", "#     #", "#######"]);
+
+        // Empuja contra la pared de arriba mientras avanza en x
+        let (x, y) = move_circle(&map, 2.0, 1.3, 1.0, -0.5, RADIUS);
+
+        assert_near(x, 3.0);
+        assert_near(y, 1.0 + RADIUS);
+        assert_clear(&map, x, y);
+    }
+
+    #[test]
+    fn rounds_convex_corner() {
+        let map = map(&["#######", "#     #", "#  #  #", "#     #", "#######"]);
+
+        // Pasa rozando la esquina de arriba a la izquierda del bloque central
+        let (x, y) = move_circle(&map, 2.5, 1.8, 1.5, 0.0, RADIUS);
+
+        assert!(x > 3.5, "se quedó trabado en la esquina en x = {}", x);
+        assert!(y <= 2.0 - RADIUS + 1e-9);
+        assert_clear(&map, x, y);
+    }
+
+    #[test]
+    fn stops_in_concave_corner() {
+        let map = map(&["#####", "#   #", "#   #", "#   #", "#####"]);
+
+        let (x, y) = move_circle(&map, 2.5, 2.5, -2.0, -2.0, RADIUS);
+
+        assert_near(x, 1.0 + RADIUS);
+        assert_near(y, 1.0 + RADIUS);
+        assert_clear(&map, x, y);
+    }
+
+    #[test]
+    fn passes_through_one_cell_gap() {
+        let map = map(&["#######", "#     #", "### ###", "#     #", "#######"]);
+
+        let (x, y) = move_circle(&map, 3.4, 1.5, 0.0, 2.0, RADIUS);
+
+        assert_near(x, 3.4);
+        assert_near(y, 3.5);
+        assert_clear(&map, x, y);
+    }
+
+    #[test]
+    fn walks_along_one_cell_corridor() {
+        let map = map(&["########", "#      #", "########"]);
+
+        // Avanza en diagonal: la componente contra las paredes se pierde
+        let (x, y) = move_circle(&map, 1.5, 1.5, 4.0, 0.8, RADIUS);
+
+        assert_near(x, 5.5);
+        assert_near(y, 2.0 - RADIUS);
+        assert_clear(&map, x, y);
+    }
+
+    #[test]
+    fn fast_diagonal_does_not_tunnel_through_corner() {
+        // (1, 1) y (2, 2) solo se tocan en una esquina entre dos paredes
+        let map = map(&["#####", "# # #", "##  #", "#   #", "#####"]);
+
+        let (x, y) = move_circle(&map, 1.5, 1.5, 5.0, 5.0, RADIUS);
+
+        assert!(x < 2.0 && y < 2.0, "atravesó la esquina hasta ({}, {})", x, y);
+        assert_clear(&map, x, y);
+    }
+
+    #[test]
+    fn zero_radius_still_moves() {
+        let map = map(&["#####", "#   #", "#####"]);
+
+        let (x, y) = move_circle(&map, 1.5, 1.5, 1.0, 0.0, 0.0);
+
+        assert_near(x, 2.5);
+        assert_near(y, 1.5);
+    }
+}
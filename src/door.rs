@@ -0,0 +1,108 @@
+use crate::inventory::KeyColor;
+
+// Fracción de la puerta que se abre o se cierra por segundo
+const DOOR_SPEED: f64 = 1.5;
+// Segundos que una puerta se queda abierta antes de cerrarse sola
+const DOOR_STAY_OPEN: f64 = 3.0;
+// A partir de esta apertura ya se puede pasar
+const PASSABLE_OPEN_AMOUNT: f64 = 0.9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorState {
+    Closed,
+    Opening,
+    Open,
+    Closing,
+}
+
+/// Resultado de intentar usar una puerta.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorUse {
+    Nothing,
+    Opened,
+    /// La puerta necesita una llave que el jugador no tiene
+    Locked(KeyColor),
+}
+
+/// Orientación de la hoja: `AlongX` cuando la puerta está entre paredes al
+/// oeste y al este (la hoja sigue el eje x, en `y = celda + 0.5`), `AlongY`
+/// cuando las paredes están al norte y al sur.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorAxis {
+    AlongX,
+    AlongY,
+}
+
+/// Puerta corrediza en medio de su celda.
+pub struct Door {
+    pub axis: DoorAxis,
+    pub state: DoorState,
+    /// 0 cerrada, 1 completamente abierta (la hoja se esconde en la pared)
+    pub open_amount: f64,
+    /// Llave necesaria para abrirla, si está cerrada con llave
+    pub lock: Option<KeyColor>,
+    timer: f64,
+}
+
+impl Door {
+    pub fn new(axis: DoorAxis) -> Self {
+        Self {
+            axis,
+            state: DoorState::Closed,
+            open_amount: 0.0,
+            lock: None,
+            timer: 0.0,
+        }
+    }
+
+    pub fn locked(axis: DoorAxis, lock: KeyColor) -> Self {
+        Self {
+            lock: Some(lock),
+            ..Self::new(axis)
+        }
+    }
+
+    pub fn is_passable(&self) -> bool {
+        self.open_amount >= PASSABLE_OPEN_AMOUNT
+    }
+
+    pub fn open(&mut self) {
+        if matches!(self.state, DoorState::Closed | DoorState::Closing) {
+            self.state = DoorState::Opening;
+        }
+    }
+
+    /// Avanza la animación. `occupied` indica que alguien está en la celda de la
+    /// puerta, en cuyo caso no se cierra.
+    pub fn update(&mut self, delta_time: f64, occupied: bool) {
+        match self.state {
+            DoorState::Closed => {}
+            DoorState::Opening => {
+                self.open_amount += DOOR_SPEED * delta_time;
+                if self.open_amount >= 1.0 {
+                    self.open_amount = 1.0;
+                    self.state = DoorState::Open;
+                    self.timer = 0.0;
+                }
+            }
+            DoorState::Open => {
+                self.timer += delta_time;
+                if self.timer >= DOOR_STAY_OPEN && !occupied {
+                    self.state = DoorState::Closing;
+                }
+            }
+            DoorState::Closing => {
+                if occupied {
+                    self.state = DoorState::Opening;
+                    return;
+                }
+
+                self.open_amount -= DOOR_SPEED * delta_time;
+                if self.open_amount <= 0.0 {
+                    self.open_amount = 0.0;
+                    self.state = DoorState::Closed;
+                }
+            }
+        }
+    }
+}
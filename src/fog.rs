@@ -0,0 +1,64 @@
+/// Cuánto se oscurecen las caras horizontales (`side == 1`) para distinguirlas
+/// de las verticales.
+pub const SIDE_SHADE: f64 = 0.75;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogMode {
+    Off,
+    /// Sin niebla antes de `start`, niebla total desde `end`
+    Linear { start: f64, end: f64 },
+    /// Se acerca a la niebla total como `1 - e^(-density * distancia)`
+    Exponential { density: f64 },
+}
+
+/// Niebla por distancia que mezcla cada píxel con `color`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: u32,
+}
+
+impl Fog {
+    pub fn off() -> Self {
+        Self {
+            mode: FogMode::Off,
+            color: 0x000000,
+        }
+    }
+
+    /// Fracción de niebla a esa distancia, de 0 (nada) a 1 (solo niebla).
+    pub fn amount(&self, distance: f64) -> f64 {
+        match self.mode {
+            FogMode::Off => 0.0,
+            FogMode::Linear { start, end } => {
+                if end <= start {
+                    return if distance >= end { 1.0 } else { 0.0 };
+                }
+                ((distance - start) / (end - start)).clamp(0.0, 1.0)
+            }
+            FogMode::Exponential { density } => 1.0 - (-density * distance.max(0.0)).exp(),
+        }
+    }
+
+    pub fn apply(&self, color: u32, distance: f64) -> u32 {
+        match self.mode {
+            FogMode::Off => color,
+            _ => mix(color, self.color, self.amount(distance)),
+        }
+    }
+}
+
+/// Multiplica cada canal por `factor`.
+pub fn shade(color: u32, factor: f64) -> u32 {
+    mix(0x000000, color, factor)
+}
+
+/// Interpola entre dos colores `0xRRGGBB`; `t = 0` da `from` y `t = 1` da `to`.
+fn mix(from: u32, to: u32, t: f64) -> u32 {
+    let channel = |shift: u32| {
+        let a = ((from >> shift) & 0xFF) as f64;
+        let b = ((to >> shift) & 0xFF) as f64;
+        ((a + (b - a) * t).round() as u32).min(255) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use crate::framebuffer::Framebuffer;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 11;
+// Separación entre caracteres y entre líneas, en píxeles de la fuente
+const LETTER_SPACING: usize = 1;
+const LINE_SPACING: usize = 2;
+// Glifo que se dibuja para caracteres que la fuente no tiene
+const MISSING: char = '?';
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Cómo dibujar un texto. Con `Align::Left` la `x` de dibujo es el borde
+/// izquierdo, con `Center` el centro y con `Right` el borde derecho.
+#[derive(Clone, Copy)]
+pub struct TextStyle {
+    pub color: u32,
+    pub scale: usize,
+    pub align: Align,
+}
+
+impl TextStyle {
+    pub fn new(color: u32, scale: usize) -> Self {
+        Self {
+            color,
+            scale: scale.max(1),
+            align: Align::Left,
+        }
+    }
+
+    pub fn aligned(self, align: Align) -> Self {
+        Self { align, ..self }
+    }
+}
+
+/// Fuente de mapa de bits de ancho fijo. Se lee una sola vez desde
+/// `font5x11.txt` y cubre el ASCII imprimible más las letras del español.
+pub struct Font {
+    // Cada fila es una máscara de bits; el bit más alto es la columna izquierda
+    glyphs: HashMap<char, [u8; GLYPH_HEIGHT]>,
+}
+
+impl Font {
+    pub fn builtin() -> Self {
+        Self::parse(include_str!("font5x11.txt")).expect("la fuente integrada es válida")
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut glyphs = HashMap::new();
+        let mut lines = source.lines().enumerate();
+
+        while let Some((number, line)) = lines.next() {
+            // Fuera de un glifo solo hay líneas vacías y comentarios
+            if line.trim().is_empty() || line.starts_with("# ") {
+                continue;
+            }
+
+            let name = line
+                .strip_prefix(": ")
+                .ok_or_else(|| format!("línea {}: se esperaba `: <carácter>`", number + 1))?;
+            let glyph = match name {
+                "space" => ' ',
+                _ => {
+                    let mut chars = name.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => return Err(format!("línea {}: nombre de glifo inválido {:?}", number + 1, name)),
+                    }
+                }
+            };
+
+            let mut rows = [0u8; GLYPH_HEIGHT];
+            for row in rows.iter_mut() {
+                let (number, line) = lines
+                    .next()
+                    .ok_or_else(|| format!("el glifo {:?} está incompleto", glyph))?;
+                if line.chars().count() != GLYPH_WIDTH || line.chars().any(|c| c != '#' && c != '.') {
+                    return Err(format!(
+                        "línea {}: se esperaban {} caracteres `#` o `.`",
+                        number + 1,
+                        GLYPH_WIDTH
+                    ));
+                }
+
+                *row = line.chars().fold(0, |bits, c| (bits << 1) | (c == '#') as u8);
+            }
+
+            glyphs.insert(glyph, rows);
+        }
+
+        if !glyphs.contains_key(&MISSING) {
+            return Err(format!("la fuente no tiene el glifo {:?}", MISSING));
+        }
+
+        Ok(Self { glyphs })
+    }
+
+    /// Avance horizontal de un carácter, espaciado incluido.
+    pub fn advance(&self, scale: usize) -> usize {
+        (GLYPH_WIDTH + LETTER_SPACING) * scale
+    }
+
+    pub fn line_height(&self, scale: usize) -> usize {
+        (GLYPH_HEIGHT + LINE_SPACING) * scale
+    }
+
+    /// Ancho y alto en píxeles del texto, que puede tener varias líneas.
+    pub fn measure(&self, text: &str, scale: usize) -> (usize, usize) {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let width = lines
+            .iter()
+            .map(|line| self.line_width(line, scale))
+            .max()
+            .unwrap_or(0);
+        let height = lines.len() * self.line_height(scale) - LINE_SPACING * scale;
+        (width, height)
+    }
+
+    fn line_width(&self, line: &str, scale: usize) -> usize {
+        match line.chars().count() {
+            0 => 0,
+            count => count * self.advance(scale) - LETTER_SPACING * scale,
+        }
+    }
+
+    /// Dibuja el texto respetando los saltos de línea; cada línea se alinea por separado.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, style: TextStyle) {
+        for (index, line) in text.split('\n').enumerate() {
+            let width = self.line_width(line, style.scale);
+            let left = match style.align {
+                Align::Left => x,
+                Align::Center => x.saturating_sub(width / 2),
+                Align::Right => x.saturating_sub(width),
+            };
+            let top = y + index * self.line_height(style.scale);
+
+            for (column, c) in line.chars().enumerate() {
+                self.draw_glyph(framebuffer, left + column * self.advance(style.scale), top, c, style);
+            }
+        }
+    }
+
+    /// Parte el texto para que quepa en `max_width` y lo dibuja dentro de la caja
+    /// que empieza en `x`. Devuelve el alto ocupado.
+    pub fn draw_wrapped(
+        &self,
+        framebuffer: &mut Framebuffer,
+        x: usize,
+        y: usize,
+        max_width: usize,
+        text: &str,
+        style: TextStyle,
+    ) -> usize {
+        let anchor = match style.align {
+            Align::Left => x,
+            Align::Center => x + max_width / 2,
+            Align::Right => x + max_width,
+        };
+        let wrapped = self.wrap(text, max_width, style.scale).join("\n");
+        self.draw(framebuffer, anchor, y, &wrapped, style);
+        self.measure(&wrapped, style.scale).1
+    }
+
+    /// Divide el texto en líneas que no pasen de `max_width` píxeles, cortando
+    /// entre palabras; una palabra que no cabe sola en una línea se corta.
+    pub fn wrap(&self, text: &str, max_width: usize, scale: usize) -> Vec<String> {
+        let max_chars = ((max_width + LETTER_SPACING * scale) / self.advance(scale)).max(1);
+        let mut lines = Vec::new();
+
+        for paragraph in text.split('\n') {
+            let mut line: Vec<char> = Vec::new();
+
+            for word in paragraph.split_whitespace() {
+                let mut word: Vec<char> = word.chars().collect();
+
+                // La palabra no cabe en una línea vacía: cortarla
+                while word.len() > max_chars {
+                    if !line.is_empty() {
+                        lines.push(line.drain(..).collect());
+                    }
+                    lines.push(word.drain(..max_chars).collect());
+                }
+
+                if !line.is_empty() && line.len() + 1 + word.len() > max_chars {
+                    lines.push(line.drain(..).collect());
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.extend(word);
+            }
+
+            lines.push(line.into_iter().collect());
+        }
+
+        lines
+    }
+
+    fn draw_glyph(&self, framebuffer: &mut Framebuffer, x: usize, y: usize, c: char, style: TextStyle) {
+        let rows = match self.glyphs.get(&c) {
+            Some(rows) => rows,
+            None => &self.glyphs[&MISSING],
+        };
+
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                    continue;
+                }
+
+                for sy in 0..style.scale {
+                    for sx in 0..style.scale {
+                        framebuffer.point(x + col * style.scale + sx, y + row * style.scale + sy, style.color);
+                    }
+                }
+            }
+        }
+    }
+}
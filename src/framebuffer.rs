@@ -1,4 +1,6 @@
+use std::path::Path;
 
+use image::{ImageResult, Rgb, RgbImage};
 
 pub struct Framebuffer {
     pub width: usize,
@@ -24,4 +26,15 @@ impl Framebuffer {
     pub fn clear(&mut self, color: u32) {
         self.buffer.fill(color);
     }
+
+    pub fn to_image(&self) -> RgbImage {
+        RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = self.buffer[y as usize * self.width + x as usize];
+            Rgb([(color >> 16) as u8, (color >> 8) as u8, color as u8])
+        })
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        self.to_image().save_with_format(path, image::ImageFormat::Png)
+    }
 }
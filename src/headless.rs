@@ -0,0 +1,165 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::map::{Map, MapError};
+use crate::minimap::Heading;
+use crate::player::Player;
+use crate::raycaster::Raycaster;
+use crate::renderer::{assets_dir, Renderer};
+
+/// Renderiza un solo cuadro sin abrir ventana y lo guarda como PNG. Sirve para
+/// pruebas con imágenes de referencia y para miniaturas de los mapas.
+///
+/// ```text
+/// game --headless --map levels/nivel1.txt --out cuadro.png
+///      [--pos X,Y] [--facing GRADOS] [--pitch GRADOS] [--eye ALTURA]
+///      [--size ANCHOxALTO] [--threads N] [--minimap norte|gira|cono]
+/// ```
+///
+/// Sin `--pos` ni `--facing` se usa la posición inicial del mapa. `--pitch` y
+/// `--eye` inclinan la vista y fijan la altura de los ojos. `--threads`
+/// fija cuántos hilos dibujan las paredes; la imagen es la misma con cualquier valor.
+/// `--minimap` elige si el minimapa deja el norte arriba, gira con el jugador
+/// o muestra el cono de visión (con el norte arriba).
+pub struct HeadlessOptions {
+    pub map: PathBuf,
+    pub output: PathBuf,
+    pub position: Option<(f64, f64)>,
+    pub facing: Option<f64>,
+    pub pitch: f64,
+    pub eye_height: Option<f64>,
+    pub width: usize,
+    pub height: usize,
+    pub threads: Option<usize>,
+    pub minimap_rotate: bool,
+    pub minimap_heading: Heading,
+}
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    Usage(String),
+    Map(MapError),
+    Image(image::ImageError),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeadlessError::Usage(message) => write!(f, "{}", message),
+            HeadlessError::Map(err) => write!(f, "{}", err),
+            HeadlessError::Image(err) => write!(f, "error de imagen: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+impl From<MapError> for HeadlessError {
+    fn from(err: MapError) -> Self {
+        HeadlessError::Map(err)
+    }
+}
+
+impl From<image::ImageError> for HeadlessError {
+    fn from(err: image::ImageError) -> Self {
+        HeadlessError::Image(err)
+    }
+}
+
+impl HeadlessOptions {
+    /// Lee las opciones a partir de los argumentos que siguen a `--headless`.
+    pub fn from_args(args: &[String]) -> Result<Self, HeadlessError> {
+        let mut map = None;
+        let mut output = None;
+        let mut position = None;
+        let mut facing = None;
+        let mut pitch = 0.0;
+        let mut eye_height = None;
+        let mut size = (640, 480);
+        let mut threads = None;
+        let mut minimap_rotate = false;
+        let mut minimap_heading = Heading::Arrow;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| HeadlessError::Usage(format!("falta el valor de {}", flag)))?;
+
+            match flag.as_str() {
+                "--map" => map = Some(PathBuf::from(value)),
+                "--out" => output = Some(PathBuf::from(value)),
+                "--pos" => position = Some(parse_pair(value, ',')?),
+                "--facing" => facing = Some(parse_value::<f64>(value)?.to_radians()),
+                "--pitch" => pitch = parse_value::<f64>(value)?.to_radians(),
+                "--eye" => eye_height = Some(parse_value(value)?),
+                "--size" => size = parse_pair(value, 'x')?,
+                "--threads" => threads = Some(parse_value(value)?),
+                "--minimap" => match value.as_str() {
+                    "norte" => minimap_rotate = false,
+                    "gira" => minimap_rotate = true,
+                    "cono" => minimap_heading = Heading::Cone,
+                    other => return Err(HeadlessError::Usage(format!("modo de minimapa desconocido {}", other))),
+                },
+                other => return Err(HeadlessError::Usage(format!("opción desconocida {}", other))),
+            }
+        }
+
+        Ok(Self {
+            map: map.ok_or_else(|| HeadlessError::Usage(String::from("falta --map")))?,
+            output: output.ok_or_else(|| HeadlessError::Usage(String::from("falta --out")))?,
+            position,
+            facing,
+            pitch,
+            eye_height,
+            width: size.0,
+            height: size.1,
+            threads,
+            minimap_rotate,
+            minimap_heading,
+        })
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(value: &str) -> Result<T, HeadlessError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| HeadlessError::Usage(format!("valor inválido {:?}", value)))
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Result<(T, T), HeadlessError> {
+    let (a, b) = value
+        .split_once(separator)
+        .ok_or_else(|| HeadlessError::Usage(format!("se esperaba `a{}b`, se encontró {:?}", separator, value)))?;
+    Ok((parse_value(a)?, parse_value(b)?))
+}
+
+/// Renderiza la escena vista por `player` en un `Renderer` nuevo con el tamaño
+/// y los hilos de `options`.
+pub fn render_frame(options: &HeadlessOptions, map: &Map, player: &Player) -> Result<Renderer, HeadlessError> {
+    let mut renderer = Renderer::with_assets(options.width, options.height, &assets_dir())?;
+    if let Some(threads) = options.threads {
+        renderer.raycaster = Raycaster::with_threads(threads);
+    }
+    renderer.set_sky(map.sky.as_deref())?;
+    renderer.minimap.rotate = options.minimap_rotate;
+    renderer.minimap.heading = options.minimap_heading;
+    renderer.render_scene(map, player);
+    Ok(renderer)
+}
+
+pub fn run(options: &HeadlessOptions) -> Result<(), HeadlessError> {
+    let map = Map::from_file(&options.map)?;
+    let (x, y) = options.position.unwrap_or((map.start_x, map.start_y));
+    let direction = options.facing.unwrap_or(map.start_direction);
+    let mut player = Player::new(x, y, direction);
+    player.look_up(options.pitch);
+    if let Some(eye_height) = options.eye_height {
+        player.eye_height = eye_height;
+    }
+
+    let renderer = render_frame(options, &map, &player)?;
+    renderer.framebuffer.save_png(&options.output)?;
+    Ok(())
+}
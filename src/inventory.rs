@@ -0,0 +1,81 @@
+/// Colores de llave. Cada uno tiene un glifo en minúscula para la llave que se
+/// recoge y uno en mayúscula para la puerta que abre.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyColor {
+    Red,
+    Blue,
+    Yellow,
+}
+
+impl KeyColor {
+    pub const ALL: [KeyColor; 3] = [KeyColor::Red, KeyColor::Blue, KeyColor::Yellow];
+
+    pub fn key_glyph(self) -> char {
+        match self {
+            KeyColor::Red => 'r',
+            KeyColor::Blue => 'u',
+            KeyColor::Yellow => 'y',
+        }
+    }
+
+    pub fn door_glyph(self) -> char {
+        self.key_glyph().to_ascii_uppercase()
+    }
+
+    pub fn from_key_glyph(glyph: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.key_glyph() == glyph)
+    }
+
+    pub fn from_door_glyph(glyph: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.door_glyph() == glyph)
+    }
+
+    /// Nombre para los mensajes ("la llave roja").
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyColor::Red => "roja",
+            KeyColor::Blue => "azul",
+            KeyColor::Yellow => "amarilla",
+        }
+    }
+
+    /// Color con el que se pinta en el HUD y en el minimapa.
+    pub fn rgb(self) -> u32 {
+        match self {
+            KeyColor::Red => 0xD22828,
+            KeyColor::Blue => 0x325ADC,
+            KeyColor::Yellow => 0xE6C828,
+        }
+    }
+}
+
+/// Lo que el jugador lleva encima. Las llaves no se gastan al abrir una puerta.
+#[derive(Default)]
+pub struct Inventory {
+    keys: Vec<KeyColor>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_key(&mut self, color: KeyColor) {
+        if !self.has_key(color) {
+            self.keys.push(color);
+        }
+    }
+
+    pub fn has_key(&self, color: KeyColor) -> bool {
+        self.keys.contains(&color)
+    }
+
+    /// Llaves recogidas, en el orden en que se tomaron.
+    pub fn keys(&self) -> &[KeyColor] {
+        &self.keys
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::map::{Map, MapError};
+use crate::maze::{self, MazeAlgorithm};
+
+/// De dónde sale el mapa de un nivel.
+pub enum LevelSource {
+    File(PathBuf),
+    /// Laberinto generado; sin semilla se usa una distinta en cada partida
+    Maze {
+        algorithm: MazeAlgorithm,
+        cells_x: usize,
+        cells_y: usize,
+        seed: Option<u64>,
+    },
+}
+
+/// Un nivel del manifiesto: su nombre visible y de dónde sale el mapa.
+pub struct LevelEntry {
+    pub name: String,
+    pub source: LevelSource,
+}
+
+/// Lista ordenada de niveles. Cada línea es `archivo | nombre a mostrar`, con
+/// rutas relativas a la carpeta del manifiesto; las líneas vacías y las que
+/// empiezan con `#` se ignoran. En lugar de un archivo se puede pedir un
+/// laberinto generado con `maze:<algoritmo>:<ancho>x<alto>:<semilla>`, donde la
+/// semilla puede ser `random`.
+pub struct LevelManifest {
+    pub levels: Vec<LevelEntry>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(std::io::Error),
+    InvalidLine { line: usize, message: String },
+    Empty,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io(err) => write!(f, "no se pudo leer el manifiesto: {}", err),
+            ManifestError::InvalidLine { line, message } => write!(f, "línea {}: {}", line, message),
+            ManifestError::Empty => write!(f, "el manifiesto no tiene niveles"),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ManifestError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(err: std::io::Error) -> Self {
+        ManifestError::Io(err)
+    }
+}
+
+/// Manifiesto de niveles: `RAYCASTER_LEVELS` si está definida o, si no,
+/// `levels/niveles.txt` en la raíz del proyecto. Igual que con las texturas,
+/// no depende de la carpeta desde la que se lance el juego.
+pub fn manifest_path() -> PathBuf {
+    match std::env::var_os("RAYCASTER_LEVELS") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("levels").join("niveles.txt"),
+    }
+}
+
+impl LevelManifest {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut levels = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (file, name) = line.split_once('|').ok_or_else(|| ManifestError::InvalidLine {
+                line: i + 1,
+                message: format!("se esperaba `archivo | nombre`, se encontró {:?}", line),
+            })?;
+            let (file, name) = (file.trim(), name.trim());
+            if file.is_empty() || name.is_empty() {
+                return Err(ManifestError::InvalidLine {
+                    line: i + 1,
+                    message: String::from("el archivo y el nombre no pueden estar vacíos"),
+                });
+            }
+
+            let source = match file.strip_prefix("maze:") {
+                Some(spec) => parse_maze(spec).ok_or_else(|| ManifestError::InvalidLine {
+                    line: i + 1,
+                    message: format!("laberinto inválido {:?}, se esperaba `maze:<algoritmo>:<ancho>x<alto>:<semilla>`", file),
+                })?,
+                None => LevelSource::File(base.join(file)),
+            };
+
+            levels.push(LevelEntry {
+                name: name.to_string(),
+                source,
+            });
+        }
+
+        if levels.is_empty() {
+            return Err(ManifestError::Empty);
+        }
+
+        Ok(Self { levels })
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn load_map(&self, index: usize) -> Result<Map, MapError> {
+        match &self.levels[index].source {
+            LevelSource::File(path) => Map::from_file(path),
+            LevelSource::Maze { algorithm, cells_x, cells_y, seed } => {
+                let seed = seed.unwrap_or_else(random_seed);
+                Ok(maze::generate(*cells_x, *cells_y, *algorithm, seed))
+            }
+        }
+    }
+}
+
+fn parse_maze(spec: &str) -> Option<LevelSource> {
+    let mut parts = spec.split(':');
+    let algorithm = MazeAlgorithm::from_name(parts.next()?)?;
+    let (cells_x, cells_y) = parts.next()?.split_once('x')?;
+    let seed = match parts.next()? {
+        "random" => None,
+        seed => Some(seed.parse().ok()?),
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(LevelSource::Maze {
+        algorithm,
+        cells_x: cells_x.parse().ok()?,
+        cells_y: cells_y.parse().ok()?,
+        seed,
+    })
+}
+
+// Semilla a partir del reloj para los laberintos `random`
+fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
@@ -0,0 +1,147 @@
+use crate::map::Map;
+use crate::raycaster::{Face, RayHit, Raycaster};
+
+/// Luz puntual, como una antorcha. `color` va de 0 a 1 por canal y `flicker`
+/// es cuánto puede bajar su intensidad al parpadear (0 = fija).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub color: [f64; 3],
+    pub flicker: f64,
+}
+
+impl Light {
+    /// Intensidad en el instante `time`, entre `1 - flicker` y 1. `seed` separa
+    /// las luces para que no parpadeen todas a la vez.
+    fn intensity(&self, time: f64, seed: f64) -> f64 {
+        let wave = (time * 13.0 + seed * 1.7).sin() * 0.5 + (time * 7.3 + seed * 4.1).sin() * 0.5;
+        1.0 - self.flicker * (wave * 0.5 + 0.5)
+    }
+}
+
+/// Luz de cada celda del mapa. Qué celdas ve cada luz y con qué peso se
+/// calcula una sola vez al cargar el nivel; `update` solo vuelve a sumar los
+/// aportes con el parpadeo del momento.
+pub struct LightMap {
+    pub ambient: f64,
+    pub lights: Vec<Light>,
+    width: usize,
+    height: usize,
+    // Por luz: índice de celda y peso según la distancia
+    reach: Vec<Vec<(usize, f64)>>,
+    tiles: Vec<[f64; 3]>,
+}
+
+impl LightMap {
+    /// Sin luces y con ambiente 1: todo se ve con el brillo de la textura.
+    pub fn uniform(width: usize, height: usize) -> Self {
+        Self {
+            ambient: 1.0,
+            lights: Vec::new(),
+            width,
+            height,
+            reach: Vec::new(),
+            tiles: vec![[1.0; 3]; width * height],
+        }
+    }
+
+    /// Precalcula qué celdas ilumina cada luz. Una celda recibe luz si el rayo
+    /// desde la luz hasta su centro no choca antes con una pared.
+    pub fn build(map: &Map, ambient: f64, lights: Vec<Light>) -> Self {
+        let raycaster = Raycaster::new();
+        let mut reach = Vec::with_capacity(lights.len());
+
+        for light in &lights {
+            let mut cells = Vec::new();
+
+            for y in 0..map.height {
+                for x in 0..map.width {
+                    if map.is_wall_cell(x as i32, y as i32) {
+                        continue;
+                    }
+
+                    let dx = x as f64 + 0.5 - light.x;
+                    let dy = y as f64 + 0.5 - light.y;
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    if distance >= light.radius {
+                        continue;
+                    }
+
+                    // Con la dirección normalizada la distancia del rayo es euclidiana
+                    let visible = distance < 0.5
+                        || raycaster
+                            .cast_ray(map, light.x, light.y, dx / distance, dy / distance)
+                            .is_none_or(|hit| hit.distance >= distance);
+                    if visible {
+                        let falloff = 1.0 - distance / light.radius;
+                        cells.push((y * map.width + x, falloff * falloff));
+                    }
+                }
+            }
+
+            reach.push(cells);
+        }
+
+        let mut light_map = Self {
+            ambient,
+            lights,
+            width: map.width,
+            height: map.height,
+            reach,
+            tiles: Vec::new(),
+        };
+        light_map.update(0.0);
+        light_map
+    }
+
+    /// Vuelve a sumar las luces con su parpadeo en el instante `time` (segundos).
+    pub fn update(&mut self, time: f64) {
+        self.tiles.clear();
+        self.tiles.resize(self.width * self.height, [self.ambient; 3]);
+
+        for (index, (light, cells)) in self.lights.iter().zip(&self.reach).enumerate() {
+            let intensity = light.intensity(time, index as f64);
+            for &(cell, weight) in cells {
+                for channel in 0..3 {
+                    self.tiles[cell][channel] += light.color[channel] * weight * intensity;
+                }
+            }
+        }
+    }
+
+    /// Verdadero si todo el mapa tiene luz blanca completa y no hace falta modular.
+    pub fn is_full_bright(&self) -> bool {
+        self.lights.is_empty() && self.ambient >= 1.0
+    }
+
+    /// Luz de la celda que contiene el punto; fuera del mapa, solo el ambiente.
+    pub fn at(&self, x: f64, y: f64) -> [f64; 3] {
+        if x < 0.0 || y < 0.0 || x as usize >= self.width || y as usize >= self.height {
+            return [self.ambient; 3];
+        }
+        self.tiles[y as usize * self.width + x as usize]
+    }
+
+    /// Luz de la cara de pared que golpeó un rayo: la de la celda libre que
+    /// está frente a esa cara.
+    pub fn at_face(&self, hit: &RayHit) -> [f64; 3] {
+        let (x, y) = match hit.face {
+            Face::West => (hit.map_x - 1, hit.map_y),
+            Face::East => (hit.map_x + 1, hit.map_y),
+            Face::North => (hit.map_x, hit.map_y - 1),
+            Face::South => (hit.map_x, hit.map_y + 1),
+        };
+        self.at(x as f64 + 0.5, y as f64 + 0.5)
+    }
+}
+
+/// Multiplica un color `0xRRGGBB` por la luz de cada canal, saturando en 255.
+pub fn modulate(color: u32, light: [f64; 3]) -> u32 {
+    let channel = |shift: u32, factor: f64| {
+        let value = ((color >> shift) & 0xFF) as f64 * factor;
+        (value.round() as u32).min(255) << shift
+    };
+    channel(16, light[0]) | channel(8, light[1]) | channel(0, light[2])
+}
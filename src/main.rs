@@ -1,25 +1,68 @@
+mod camera;
+mod collision;
+mod door;
+mod fog;
+mod font;
 mod framebuffer;
+mod headless;
+mod inventory;
+mod levels;
+mod lighting;
 mod map;
+mod maze;
+mod minimap;
+mod mouse_look;
+mod pathfinding;
 mod player;
 mod raycaster;
 mod renderer;
+mod sprite;
+mod textures;
+mod validation;
 
+use crate::door::DoorUse;
+use crate::font::{Align, Font, TextStyle};
+use crate::levels::{LevelManifest, ManifestError};
 use crate::map::Map;
-use crate::player::Player;
+use crate::minimap::Heading;
+use crate::mouse_look::MouseLook;
+use crate::player::{MovementInput, Player};
 use crate::renderer::Renderer;
 
-use minifb::{Key, Window, WindowOptions, MouseButton};
+use minifb::{Key, KeyRepeat, Window, WindowOptions, MouseButton};
 
 
 
-// Estado del juego: pantalla de bienvenida, nivel 1, nivel 2
+// Estado del juego: pantalla de bienvenida, un nivel del manifiesto o la pantalla de éxito
 pub enum GameState {
     WelcomeScreen,
-    Level1,
-    Level2,
-    SuccessScreen,
+    Playing { level_index: usize },
+    SuccessScreen { level_index: usize },
 }
 
+// Ayuda de ruta en el minimapa, se alterna con la tecla H
+#[derive(Clone, Copy, PartialEq)]
+pub enum HintMode {
+    Off,
+    NextCells(usize),
+    FullRoute,
+}
+
+// Posición y tamaño de los botones de las pantallas de menú
+const BUTTON_X: usize = 100;
+const BUTTON_Y: usize = 120;
+const BUTTON_WIDTH: usize = 260;
+const BUTTON_HEIGHT: usize = 50;
+const BUTTON_SPACING: usize = 70;
+// Con muchos niveles los botones se juntan hasta este paso; si aun así no caben, se pagina
+const BUTTON_MIN_SPACING: usize = 56;
+const BUTTON_COLORS: [u32; 2] = [0x007BFF, 0xFF5722];
+// Botones para cambiar de página, a la derecha de la lista de niveles
+const PAGE_BUTTON_X: usize = BUTTON_X + BUTTON_WIDTH + 20;
+const PAGE_BUTTON_WIDTH: usize = 140;
+// Alto que ocupa el texto de controles al pie de la pantalla de bienvenida
+const CONTROLS_HEIGHT: usize = 110;
+
 pub struct Game {
     width: usize,
     height: usize,
@@ -27,7 +70,15 @@ pub struct Game {
     renderer: Renderer,
     player: Player,
     map: Map,
-    target_fps: usize,
+    levels: LevelManifest,
+    mouse_was_down: bool,
+    menu_page: usize, // Página de la lista de niveles en la pantalla de bienvenida
+    menu_error: Option<String>, // Por qué no se pudo cargar el último nivel elegido
+    mouse_look: MouseLook,
+    font: Font,
+    hint_mode: HintMode,
+    par_length: Option<usize>, // Largo de la ruta óptima del nivel actual, en casillas
+    message: Option<(String, f64)>, // Aviso durante el juego y segundos que le quedan en pantalla
     state: GameState, // Estado del juego
 }
 
@@ -42,7 +93,9 @@ impl Game {
         distance < 0.5
     }
 
-    pub fn new(width: usize, height: usize) -> Self {
+    /// Abre la ventana del juego. Falla si el manifiesto de niveles no se puede leer.
+    pub fn new(width: usize, height: usize) -> Result<Self, ManifestError> {
+        let levels = LevelManifest::from_file(levels::manifest_path())?;
         let window = Window::new(
             "3D Raycaster",
             width,
@@ -52,19 +105,28 @@ impl Game {
         .unwrap();
 
         let renderer = Renderer::new(width, height);
-        let map = Map::new_level_1(); // Inicializa con el mapa del nivel 1
+        // Mapa provisional hasta que se elija un nivel; los del manifiesto se cargan en `start_level`
+        let map = Map::placeholder();
         let player = Player::new(3.0, 3.0, 0.0);
 
-        Self {
+        Ok(Self {
             width,
             height,
             window,
             renderer,
             player,
             map,
-            target_fps: 160,
+            levels,
+            mouse_was_down: false,
+            menu_page: 0,
+            menu_error: None,
+            mouse_look: MouseLook::new(),
+            font: Font::builtin(),
+            hint_mode: HintMode::Off,
+            par_length: None,
+            message: None,
             state: GameState::WelcomeScreen, // Inicia en la pantalla de bienvenida
-        }
+        })
     }
 
     pub fn run(&mut self) {
@@ -80,9 +142,8 @@ impl Game {
                 GameState::WelcomeScreen => {
                     self.show_welcome_screen();
                 }
-                GameState::Level1 => self.run_level(1),
-                GameState::Level2 => self.run_level(2),
-                GameState::SuccessScreen => self.show_success_screen(), // Pantalla de éxito
+                GameState::Playing { level_index } => self.run_level(level_index),
+                GameState::SuccessScreen { level_index } => self.show_success_screen(level_index), // Pantalla de éxito
             }
     
             // Calcular los frames por segundo (FPS)
@@ -105,20 +166,45 @@ impl Game {
         // Limpiar el framebuffer una sola vez
         framebuffer.clear(0x000000); // Fondo negro
     
-        // Dibujar todo el contenido de la pantalla de bienvenida
-        self.draw_text(100, 50, "WELCOME SELECCIONE UN NIVEL", 0xFFFFFF); // Dibujar "WELCOME"
-        self.draw_button(100, 150, 200, 50, "1", 0xFFFFFF, 0x007BFF); // Botón Level 1
-        self.draw_button(100, 250, 200, 50, "2", 0xFFFFFF, 0xFF5722); // Botón Level 2
+        // Dibujar todo el contenido de la pantalla de bienvenida: un botón por nivel de la página actual
+        self.draw_text(100, 50, "¡Bienvenido! Seleccione un nivel", 0xFFFFFF); // Dibujar la bienvenida
+        if let Some(error) = &self.menu_error {
+            let style = TextStyle::new(0xFF6060, 1);
+            self.font.draw_wrapped(&mut self.renderer.framebuffer, 100, 78, self.width - 140, error, style);
+        }
+
+        let (per_page, spacing) = self.menu_layout();
+        let pages = self.levels.len().div_ceil(per_page);
+        self.menu_page = self.menu_page.min(pages - 1);
+        let first = self.menu_page * per_page;
+        let shown = (self.levels.len() - first).min(per_page);
+        for row in 0..shown {
+            let index = first + row;
+            let label = self.levels.levels[index].name.clone();
+            let color = BUTTON_COLORS[index % BUTTON_COLORS.len()];
+            self.draw_button(BUTTON_X, Self::button_y(row, spacing), BUTTON_WIDTH, BUTTON_HEIGHT, &label, 0xFFFFFF, color);
+        }
+        if pages > 1 {
+            for (row, label) in ["Anterior", "Siguiente"].into_iter().enumerate() {
+                self.draw_button(PAGE_BUTTON_X, Self::button_y(row, spacing), PAGE_BUTTON_WIDTH, BUTTON_HEIGHT, label, 0xFFFFFF, 0x444444);
+            }
+            let page = format!("Página {}/{}", self.menu_page + 1, pages);
+            self.draw_text(PAGE_BUTTON_X, Self::button_y(2, spacing), &page, 0xAAAAAA);
+        }
+    
+        let controls = "W/S avanzar, A/D girar, Q/E de lado, Shift correr, C agacharse, J saltar, RePág/AvPág mirar arriba y abajo, Espacio abre puertas, M captura el mouse, N/V/+/- minimapa.";
+        let style = TextStyle::new(0xAAAAAA, 2).aligned(Align::Center);
+        self.font.draw_wrapped(&mut self.renderer.framebuffer, 40, self.height - CONTROLS_HEIGHT, self.width - 80, controls, style);
     
         // Verificar si se hizo clic en algún botón **antes de actualizar el framebuffer**
-        if let Some((mouse_x, mouse_y)) = self.window.get_mouse_pos(minifb::MouseMode::Clamp) {
-            if self.window.get_mouse_down(MouseButton::Left) {
-                if self.is_inside_button(mouse_x, mouse_y, 100, 150, 200, 50) {
-                    self.map = Map::new_level_1(); // Cargar mapa del nivel 1
-                    self.state = GameState::Level1;
-                } else if self.is_inside_button(mouse_x, mouse_y, 100, 250, 200, 50) {
-                    self.map = Map::new_level_2(); // Cargar mapa del nivel 2
-                    self.state = GameState::Level2;
+        if let Some(click) = self.mouse_click() {
+            if let Some(row) = Self::button_at(click, BUTTON_X, BUTTON_WIDTH, shown, spacing) {
+                self.start_level(first + row);
+            } else if pages > 1 {
+                match Self::button_at(click, PAGE_BUTTON_X, PAGE_BUTTON_WIDTH, 2, spacing) {
+                    Some(0) => self.menu_page = self.menu_page.saturating_sub(1),
+                    Some(_) => self.menu_page = (self.menu_page + 1).min(pages - 1),
+                    None => {}
                 }
             }
         }
@@ -126,351 +212,90 @@ impl Game {
         // Actualizar el buffer en la ventana solo después de procesar los clics
         self.window.update_with_buffer(&self.renderer.framebuffer.buffer, self.width, self.height).unwrap();
     }
-    
-    
 
-        fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u32) {
-            let font = vec![
-                // A-Z representados en un formato de 5x7 píxeles
-                // A
-                [
-                    " 000 ",
-                    "0   0",
-                    "0   0",
-                    "00000",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                ],
-                // B
-                [
-                    "0000 ",
-                    "0   0",
-                    "0000 ",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0000 ",
-                ],
-                // C
-                [
-                    " 0000",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    " 0000",
-                ],
-                // D
-                [
-                    "0000 ",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0000 ",
-                ],
-                // E
-                [
-                    "00000",
-                    "0    ",
-                    "0000 ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "00000",
-                ],
-                // F
-                [
-                    "00000",
-                    "0    ",
-                    "0000 ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                ],
-                // G
-                [
-                    " 0000",
-                    "0    ",
-                    "0  00",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    " 0000",
-                ],
-                // H
-                [
-                    "0   0",
-                    "0   0",
-                    "00000",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                ],
-                // I
-                [
-                    " 000 ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    " 000 ",
-                ],
-                // J
-                [
-                    "   00",
-                    "    0",
-                    "    0",
-                    "    0",
-                    "0   0",
-                    "0   0",
-                    " 000 ",
-                ],
-                // K
-                [
-                    "0   0",
-                    "0  0 ",
-                    "000  ",
-                    "0 0  ",
-                    "0  0 ",
-                    "0   0",
-                    "0   0",
-                ],
-                // L
-                [
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                    "00000",
-                ],
-                // M
-                [
-                    "0   0",
-                    "00 00",
-                    "0 0 0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                ],
-                // N
-                [
-                    "0   0",
-                    "00  0",
-                    "0 0 0",
-                    "0  00",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                ],
-                // O
-                [
-                    " 000 ",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    " 000 ",
-                ],
-                // P
-                [
-                    "0000 ",
-                    "0   0",
-                    "0   0",
-                    "0000 ",
-                    "0    ",
-                    "0    ",
-                    "0    ",
-                ],
-                // Q
-                [
-                    " 000 ",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0 0 0",
-                    "0  00",
-                    " 0000",
-                ],
-                // R
-                [
-                    "0000 ",
-                    "0   0",
-                    "0   0",
-                    "0000 ",
-                    "0  0 ",
-                    "0   0",
-                    "0   0",
-                ],
-                // S
-                [
-                    " 0000",
-                    "0    ",
-                    "0    ",
-                    " 000 ",
-                    "    0",
-                    "    0",
-                    "0000 ",
-                ],
-                // T
-                [
-                    "00000",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                ],
-                // U
-                [
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    " 000 ",
-                ],
-                // V
-                [
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    " 0 0 ",
-                    "  0  ",
-                ],
-                // W
-                [
-                    "0   0",
-                    "0   0",
-                    "0   0",
-                    "0 0 0",
-                    "0 0 0",
-                    "0 0 0",
-                    " 0 0 ",
-                ],
-                // X
-                [
-                    "0   0",
-                    "0   0",
-                    " 0 0 ",
-                    "  0  ",
-                    " 0 0 ",
-                    "0   0",
-                    "0   0",
-                ],
-                // Y
-                [
-                    "0   0",
-                    "0   0",
-                    " 0 0 ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                ],
-                // Z
-                [
-                    "00000",
-                    "    0",
-                    "   0 ",
-                    "  0  ",
-                    " 0   ",
-                    "0    ",
-                    "00000",
-                ],
-                // 1
-                [
-                    "  0  ",
-                    " 00  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    "  0  ",
-                    " 000 ",
-                ],
-                // 2
-                [
-                    " 000 ",
-                    "0   0",
-                    "    0",
-                    "   0 ",
-                    "  0  ",
-                    " 0   ",
-                    "00000",
-                ],
-            ];
-    
-            let scale = 2; // Escala de los caracteres
-    let char_width = 5; // Ancho de cada carácter en la fuente
-    let char_height = 7; // Altura de cada carácter en la fuente
-
-    for (i, c) in text.chars().enumerate() {
-        if let Some(font_index) = match c {
-            'A'..='Z' => Some((c as u8 - b'A') as usize),
-            '1' => Some(26), // Índice del número 1
-            '2' => Some(27), // Índice del número 2
-            _ => None,
-        } {
-            let letter = &font[font_index];
-            for (row, line) in letter.iter().enumerate() {
-                for (col, ch) in line.chars().enumerate() {
-                    if ch == '0' {
-                        for sx in 0..scale {
-                            for sy in 0..scale {
-                                self.renderer.framebuffer.point(
-                                    x + i * (char_width + 1) * scale + col * scale + sx,
-                                    y + row * scale + sy,
-                                    color,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
+    fn button_y(index: usize, spacing: usize) -> usize {
+        BUTTON_Y + index * spacing
+    }
+
+    // Cuántos botones de nivel caben por página y el paso vertical entre ellos,
+    // según la cantidad de niveles y el alto de la ventana. Los botones se
+    // juntan hasta `BUTTON_MIN_SPACING` antes de repartirse en páginas.
+    fn menu_layout(&self) -> (usize, usize) {
+        let count = self.levels.len();
+        // Espacio para los botones que van debajo del primero
+        let room = self
+            .height
+            .saturating_sub(CONTROLS_HEIGHT + 10)
+            .saturating_sub(BUTTON_Y + BUTTON_HEIGHT);
+        if count <= 1 {
+            return (1, BUTTON_SPACING);
         }
+
+        let spacing = (room / (count - 1)).clamp(BUTTON_MIN_SPACING, BUTTON_SPACING);
+        ((room / spacing + 1).min(count), spacing)
     }
+
+    // Posición del mouse si el botón izquierdo se acaba de presionar. Solo
+    // cuenta el momento de presionar, para que un clic no atraviese dos pantallas.
+    fn mouse_click(&mut self) -> Option<(f32, f32)> {
+        let mouse_down = self.window.get_mouse_down(MouseButton::Left);
+        let pressed = mouse_down && !self.mouse_was_down;
+        self.mouse_was_down = mouse_down;
+        if !pressed {
+            return None;
         }
+
+        self.window.get_mouse_pos(minifb::MouseMode::Clamp)
+    }
+
+    // Índice del botón, de `count` apilados en la columna `x` con `button_y`, que contiene el clic
+    fn button_at(click: (f32, f32), x: usize, width: usize, count: usize, spacing: usize) -> Option<usize> {
+        let (mouse_x, mouse_y) = click;
+        (0..count).find(|&index| {
+            Self::is_inside_button(mouse_x, mouse_y, x, Self::button_y(index, spacing), width, BUTTON_HEIGHT)
+        })
+    }
     
-        fn draw_button(&mut self, x: usize, y: usize, width: usize, height: usize, text: &str, text_color: u32, background_color: u32) {
-            let framebuffer = &mut self.renderer.framebuffer;
-        
-            // Dibujar el fondo del botón
-            for py in 0..height {
-                for px in 0..width {
-                    framebuffer.point(x + px, y + py, background_color);
-                }
+    
+
+    fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u32) {
+        self.font.draw(&mut self.renderer.framebuffer, x, y, text, TextStyle::new(color, 2));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_button(&mut self, x: usize, y: usize, width: usize, height: usize, text: &str, text_color: u32, background_color: u32) {
+        let framebuffer = &mut self.renderer.framebuffer;
+    
+        // Dibujar el fondo del botón
+        for py in 0..height {
+            for px in 0..width {
+                framebuffer.point(x + px, y + py, background_color);
             }
-        
-            // Dibujar el texto del botón
-            self.draw_text(x + 20, y + 15, text, text_color); // Ajustar la posición para centrar el texto
         }
+    
+        // Dibujar el texto del botón centrado
+        let style = TextStyle::new(text_color, 2).aligned(Align::Center);
+        let text_height = self.font.measure(text, style.scale).1;
+        let text_y = y + height.saturating_sub(text_height) / 2;
+        self.font.draw(framebuffer, x + width / 2, text_y, text, style);
+    }
         
 
-    fn is_inside_button(&self, mouse_x: f32, mouse_y: f32, button_x: usize, button_y: usize, button_width: usize, button_height: usize) -> bool {
+    fn is_inside_button(mouse_x: f32, mouse_y: f32, button_x: usize, button_y: usize, button_width: usize, button_height: usize) -> bool {
         mouse_x >= button_x as f32
             && mouse_x <= (button_x + button_width) as f32
             && mouse_y >= button_y as f32
             && mouse_y <= (button_y + button_height) as f32
     }
 
-    pub fn run_level(&mut self, level: usize) {
+    pub fn run_level(&mut self, level_index: usize) {
+        // El nombre del mapa incluye la semilla en los laberintos generados
+        self.window.set_title(&format!("3D Raycaster - {}", self.map.name));
+
         let mut last_frame_time = std::time::Instant::now();
         let frame_duration = std::time::Duration::from_secs_f64(1.0 / 60.0);
         let mut goal_reached_flag = false; // Bandera para determinar si la meta fue alcanzada
+        let level_start = std::time::Instant::now(); // Reloj del parpadeo de las luces
     
         while self.window.is_open() && !self.window.is_key_down(Key::Escape) {
             let now = std::time::Instant::now();
@@ -480,13 +305,12 @@ impl Game {
             // Si la meta ha sido alcanzada, evitamos seguir procesando el nivel
             if goal_reached_flag {
                 std::thread::sleep(std::time::Duration::from_millis(100));
-                self.state = GameState::SuccessScreen;
+                self.state = GameState::SuccessScreen { level_index };
                 break;
             }
     
             // Obtener las coordenadas de la meta y del jugador
-            let goal_x = self.map.end_x;
-            let goal_y = self.map.end_y;
+            let (goal_x, goal_y) = self.map.goal_position();
             let player_x = self.player.x;
             let player_y = self.player.y;
     
@@ -501,8 +325,17 @@ impl Game {
     
             // Actualizar el estado del juego
             self.handle_input();
+            self.map.update_doors(delta_time, self.player.x, self.player.y, self.player.radius);
             self.player.update(&self.map, delta_time);
+            if let Some(color) = self.map.take_key(self.player.x, self.player.y) {
+                self.player.inventory.add_key(color);
+                self.show_message(format!("Recogiste la llave {}", color.name()));
+            }
+            self.update_hint();
+            self.map.lighting.update(level_start.elapsed().as_secs_f64());
             self.renderer.render_scene(&self.map, &self.player);
+            self.draw_message(delta_time);
+            self.draw_level_name();
             self.window.update_with_buffer(&self.renderer.framebuffer.buffer, self.width, self.height).unwrap();
     
             let frame_elapsed = now.elapsed();
@@ -510,69 +343,200 @@ impl Game {
                 std::thread::sleep(frame_duration - frame_elapsed);
             }
         }
+
+        // Los menús necesitan el cursor visible
+        self.mouse_look.set_captured(&mut self.window, false);
     }
     
     
     
     
     fn handle_input(&mut self) {
-        if self.window.is_key_down(Key::W) || self.window.is_key_down(Key::Up) {
-            self.player.move_forward(0.1, &self.map);
+        // Cada eje vale 1 o -1 según la tecla; `Player::update` lo escala por delta_time
+        let axis = |window: &Window, negative: &[Key], positive: &[Key]| {
+            let pressed = |keys: &[Key]| keys.iter().any(|&key| window.is_key_down(key));
+            (pressed(positive) as i32 - pressed(negative) as i32) as f64
+        };
+
+        let input = MovementInput {
+            forward: axis(&self.window, &[Key::S, Key::Down], &[Key::W, Key::Up]),
+            strafe: axis(&self.window, &[Key::Q], &[Key::E]),
+            turn: axis(&self.window, &[Key::A, Key::Left], &[Key::D, Key::Right]),
+            look: axis(&self.window, &[Key::PageDown], &[Key::PageUp]),
+            running: self.window.is_key_down(Key::LeftShift) || self.window.is_key_down(Key::RightShift),
+            crouching: self.window.is_key_down(Key::C),
+            jump: self.window.is_key_pressed(Key::J, KeyRepeat::No),
+        };
+        self.player.set_input(input);
+
+        // Inicio vuelve a mirar al frente
+        if self.window.is_key_pressed(Key::Home, KeyRepeat::No) {
+            self.player.center_view();
+        }
+
+        // Mouse-look: M captura/suelta el cursor, I y K invierten los ejes, [ y ] ajustan la sensibilidad
+        if self.window.is_key_pressed(Key::M, KeyRepeat::No) {
+            self.mouse_look.toggle(&mut self.window);
+        }
+        if self.window.is_key_pressed(Key::I, KeyRepeat::No) {
+            self.mouse_look.invert_x = !self.mouse_look.invert_x;
+        }
+        if self.window.is_key_pressed(Key::K, KeyRepeat::No) {
+            self.mouse_look.invert_y = !self.mouse_look.invert_y;
+        }
+        if self.window.is_key_pressed(Key::LeftBracket, KeyRepeat::Yes) {
+            self.mouse_look.sensitivity = (self.mouse_look.sensitivity / 1.25).max(0.0005);
+        }
+        if self.window.is_key_pressed(Key::RightBracket, KeyRepeat::Yes) {
+            self.mouse_look.sensitivity = (self.mouse_look.sensitivity * 1.25).min(0.05);
+        }
+        let (yaw, pitch) = self.mouse_look.update(&mut self.window);
+        self.player.turn_right(yaw);
+        self.player.look_up(pitch);
+
+        // Espacio abre la puerta que está enfrente
+        if self.window.is_key_pressed(Key::Space, KeyRepeat::No) {
+            let used = self.map.use_door(self.player.x, self.player.y, self.player.direction, &self.player.inventory);
+            if let DoorUse::Locked(color) = used {
+                self.show_message(format!("Necesitas la llave {}", color.name()));
+            }
+        }
+
+        // Minimapa: N alterna norte arriba o girar con el jugador, V flecha o
+        // cono de visión, + y - el zoom, el teclado numérico lo corre y 5 lo centra
+        let minimap = &mut self.renderer.minimap;
+        if self.window.is_key_pressed(Key::N, KeyRepeat::No) {
+            minimap.rotate = !minimap.rotate;
         }
-        if self.window.is_key_down(Key::S) || self.window.is_key_down(Key::Down) {
-            self.player.move_backward(0.1, &self.map);
+        if self.window.is_key_pressed(Key::V, KeyRepeat::No) {
+            minimap.heading = match minimap.heading {
+                Heading::Arrow => Heading::Cone,
+                Heading::Cone => Heading::Arrow,
+            };
         }
-        if self.window.is_key_down(Key::A) || self.window.is_key_down(Key::Left) {
-            self.player.turn_left(0.05);
+        if self.window.is_key_pressed(Key::Equal, KeyRepeat::Yes) || self.window.is_key_pressed(Key::NumPadPlus, KeyRepeat::Yes) {
+            minimap.zoom_by(1.25);
         }
-        if self.window.is_key_down(Key::D) || self.window.is_key_down(Key::Right) {
-            self.player.turn_right(0.05);
+        if self.window.is_key_pressed(Key::Minus, KeyRepeat::Yes) || self.window.is_key_pressed(Key::NumPadMinus, KeyRepeat::Yes) {
+            minimap.zoom_by(1.0 / 1.25);
+        }
+        for (key, dx, dy) in [(Key::NumPad4, -1.0, 0.0), (Key::NumPad6, 1.0, 0.0), (Key::NumPad8, 0.0, -1.0), (Key::NumPad2, 0.0, 1.0)] {
+            if self.window.is_key_pressed(key, KeyRepeat::Yes) {
+                minimap.scroll_by(dx, dy);
+            }
+        }
+        if self.window.is_key_pressed(Key::NumPad5, KeyRepeat::No) {
+            minimap.center();
+        }
+
+        // H alterna la ayuda: próximas casillas, ruta completa, apagada
+        if self.window.is_key_pressed(Key::H, KeyRepeat::No) {
+            self.hint_mode = match self.hint_mode {
+                HintMode::Off => HintMode::NextCells(5),
+                HintMode::NextCells(_) => HintMode::FullRoute,
+                HintMode::FullRoute => HintMode::Off,
+            };
+        }
+    }
+
+    fn show_message(&mut self, text: String) {
+        self.message = Some((text, 2.0));
+    }
+
+    // Dibuja el aviso actual centrado sobre el HUD y descuenta su tiempo
+    fn draw_message(&mut self, delta_time: f64) {
+        if let Some((text, remaining)) = &mut self.message {
+            let style = TextStyle::new(0xFFFFFF, 2).aligned(Align::Center);
+            self.font.draw(&mut self.renderer.framebuffer, self.width / 2, self.height - 70, text, style);
+
+            *remaining -= delta_time;
+            if *remaining <= 0.0 {
+                self.message = None;
+            }
         }
     }
 
-    pub fn start_level(&mut self, level: usize) {
-        match level {
-            1 => {
-                self.map = Map::new_level_1();
-                self.player.x = self.map.start_x;
-                self.player.y = self.map.start_y;
-                self.state = GameState::Level1;
+    // Nombre del nivel alineado a la derecha en la franja del HUD
+    fn draw_level_name(&mut self) {
+        let style = TextStyle::new(0xAAAAAA, 1).aligned(Align::Right);
+        self.font.draw(&mut self.renderer.framebuffer, self.width - 8, self.height - 20, &self.map.name, style);
+    }
+
+    // Recalcula la ruta de ayuda desde la posición actual del jugador
+    fn update_hint(&mut self) {
+        let route = match self.hint_mode {
+            HintMode::Off => None,
+            _ => {
+                pathfinding::route_to_goal(&self.map, self.player.x, self.player.y, self.player.inventory.keys())
             }
-            2 => {
-                self.map = Map::new_level_2();
-                self.player.x = self.map.start_x;
-                self.player.y = self.map.start_y;
-                self.state = GameState::Level2;
+        };
+
+        self.renderer.hint_route = match (self.hint_mode, route) {
+            (HintMode::NextCells(count), Some(route)) => route.into_iter().skip(1).take(count).collect(),
+            (HintMode::FullRoute, Some(route)) => route,
+            _ => Vec::new(),
+        };
+    }
+
+    /// Carga el nivel y empieza a jugarlo. Si el mapa no se puede cargar se
+    /// vuelve al menú mostrando el error.
+    pub fn start_level(&mut self, level_index: usize) {
+        self.map = match self.levels.load_map(level_index) {
+            Ok(map) => map,
+            Err(err) => {
+                let name = &self.levels.levels[level_index].name;
+                self.menu_error = Some(format!("No se pudo cargar \"{}\": {}", name, err));
+                self.state = GameState::WelcomeScreen;
+                return;
             }
-            _ => {} // Handle invalid levels (optional)
+        };
+        self.menu_error = None;
+        self.player.place(self.map.start_x, self.map.start_y, self.map.start_direction);
+        self.player.inventory.clear();
+        // Un cielo que no carga no impide jugar: queda el color liso
+        if let Err(err) = self.renderer.set_sky(self.map.sky.as_deref()) {
+            eprintln!("no se pudo cargar el cielo {:?}: {}", self.map.sky.as_deref().unwrap_or(""), err);
+            self.renderer.sky_texture = None;
         }
+        self.renderer.minimap.reset(&self.map);
+        self.message = None;
+        self.par_length = pathfinding::route_to_goal(&self.map, self.map.start_x, self.map.start_y, &[])
+            .map(|route| route.len() - 1);
+        self.state = GameState::Playing { level_index };
     }
-    pub fn show_success_screen(&mut self) {
+
+    pub fn show_success_screen(&mut self, level_index: usize) {
         let framebuffer = &mut self.renderer.framebuffer;
     
         // Clear the framebuffer
         framebuffer.clear(0x000000); // Black background
     
-        // Draw success screen content
-        self.draw_text(100, 50, "¡FELICIDADES! NIVEL COMPLETADO", 0xFFFFFF);
-        self.draw_button(100, 150, 200, 50, "1", 0xFFFFFF, 0x007BFF); // Level 1 Button
-        self.draw_button(100, 250, 200, 50, "2", 0xFFFFFF, 0xFF5722); // Level 2 Button
+        // Draw success screen content: next level (if any) and back to the menu
+        let next_level = level_index + 1;
+        let has_next = next_level < self.levels.len();
+        let mut buttons = Vec::new();
+        if has_next {
+            buttons.push("Siguiente nivel");
+        }
+        buttons.push("Menú");
+
+        self.draw_text(100, 50, "¡Felicidades! Nivel completado", 0xFFFFFF);
+        if let Some(par) = self.par_length {
+            self.draw_text(100, 80, &format!("Ruta óptima: {} casillas", par), 0xAAAAAA);
+        }
+        for (index, label) in buttons.iter().enumerate() {
+            let color = BUTTON_COLORS[index % BUTTON_COLORS.len()];
+            self.draw_button(BUTTON_X, Self::button_y(index, BUTTON_SPACING), BUTTON_WIDTH, BUTTON_HEIGHT, label, 0xFFFFFF, color);
+        }
     
         // Check if any button was clicked
-        if let Some((mouse_x, mouse_y)) = self.window.get_mouse_pos(minifb::MouseMode::Clamp) {
-            if self.window.get_mouse_down(MouseButton::Left) {
-                if self.is_inside_button(mouse_x, mouse_y, 100, 150, 200, 50) {
-                    self.map = Map::new_level_1(); // Load level 1
-                    self.player.x = self.map.start_x;
-                    self.player.y = self.map.start_y;
-                    self.state = GameState::Level1;
-                } else if self.is_inside_button(mouse_x, mouse_y, 100, 250, 200, 50) {
-                    self.map = Map::new_level_2(); // Load level 2
-                    self.player.x = self.map.start_x;
-                    self.player.y = self.map.start_y;
-                    self.state = GameState::Level2;
-                }
-            }
+        let clicked = self
+            .mouse_click()
+            .and_then(|click| Self::button_at(click, BUTTON_X, BUTTON_WIDTH, buttons.len(), BUTTON_SPACING));
+        match clicked {
+            Some(0) if has_next => self.start_level(next_level),
+            Some(_) => self.state = GameState::WelcomeScreen,
+            None => {}
         }
     
         // Update window buffer
@@ -581,6 +545,23 @@ impl Game {
 }
 
 fn main() {
-    let mut game = Game::new(640, 480);
-    game.run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // Modo sin ventana: renderizar un cuadro a PNG y salir
+    if args.first().map(String::as_str) == Some("--headless") {
+        let result = headless::HeadlessOptions::from_args(&args[1..]).and_then(|options| headless::run(&options));
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match Game::new(640, 480) {
+        Ok(mut game) => game.run(),
+        Err(err) => {
+            eprintln!("no se pudo cargar la lista de niveles {}: {}", levels::manifest_path().display(), err);
+            std::process::exit(1);
+        }
+    }
 }
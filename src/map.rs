@@ -1,63 +1,527 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::door::{Door, DoorAxis, DoorUse};
+use crate::fog::{Fog, FogMode};
+use crate::inventory::{Inventory, KeyColor};
+use crate::lighting::{Light, LightMap};
+use crate::sprite::Sprite;
+use crate::validation::ValidationReport;
+
+// Glifos que puede contener la rejilla de un nivel
+const WALL: char = '#';
+const BRICK: char = 'B';
+const METAL: char = 'M';
+const EMPTY: char = ' ';
+const START: char = 'S';
+const GOAL: char = 'E';
+pub const DOOR: char = 'D';
+
+// Alcance de la tecla de usar para abrir puertas
+const USE_REACH: f64 = 1.0;
+// Distancia desde el centro de una llave a la que el jugador la recoge
+const PICKUP_RADIUS: f64 = 0.5;
+
 pub struct Map {
+    pub name: String,
     pub width: usize,
     pub height: usize,
     pub grid: Vec<Vec<char>>,
     pub start_x: f64,
     pub start_y: f64,
+    pub start_direction: f64,
     pub end_x: f64,
     pub end_y: f64,
+    pub sprites: Vec<Sprite>,
+    /// Estado de cada puerta, indexado por su celda `(x, y)`
+    pub doors: HashMap<(usize, usize), Door>,
+    /// Celdas techadas, `[y][x]`; donde no hay techo se ve el cielo
+    pub ceiling: Vec<Vec<bool>>,
+    /// Imagen panorámica del cielo, relativa a la carpeta de texturas
+    pub sky: Option<String>,
+    pub fog: Fog,
+    pub lighting: LightMap,
+    /// Altura de la pared de cada celda, `[y][x]`, en unidades (1 = normal)
+    pub wall_heights: Vec<Vec<f64>>,
+}
+
+/// Errores al leer un nivel en formato de texto. Las líneas se cuentan desde 1
+/// y se refieren al archivo completo, cabecera incluida.
+#[derive(Debug)]
+pub enum MapError {
+    Io(std::io::Error),
+    InvalidHeader { line: usize, message: String },
+    EmptyGrid,
+    RaggedRow { line: usize, expected: usize, found: usize },
+    UnknownGlyph { line: usize, column: usize, glyph: char },
+    MissingStart,
+    MissingGoal,
+    Invalid(ValidationReport),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(err) => write!(f, "no se pudo leer el nivel: {}", err),
+            MapError::InvalidHeader { line, message } => {
+                write!(f, "línea {}: cabecera inválida: {}", line, message)
+            }
+            MapError::EmptyGrid => write!(f, "el nivel no tiene filas"),
+            MapError::RaggedRow { line, expected, found } => write!(
+                f,
+                "línea {}: la fila tiene {} columnas pero se esperaban {}",
+                line, found, expected
+            ),
+            MapError::UnknownGlyph { line, column, glyph } => {
+                write!(f, "línea {}, columna {}: glifo desconocido {:?}", line, column, glyph)
+            }
+            MapError::MissingStart => {
+                write!(f, "falta la posición inicial (cabecera `start:` o glifo 'S')")
+            }
+            MapError::MissingGoal => {
+                write!(f, "falta la meta (cabecera `goal:` o glifo 'E')")
+            }
+            MapError::Invalid(report) => write!(f, "el nivel no es válido:\n{}", report),
+        }
+    }
+}
+
+impl std::error::Error for MapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MapError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MapError {
+    fn from(err: std::io::Error) -> Self {
+        MapError::Io(err)
+    }
+}
+
+/// Formato de los niveles:
+///
+/// ```text
+/// name: Nivel 1
+/// start: 1.5 1.5
+/// facing: 0
+/// goal: 8 8
+///
+/// ##########
+/// #S   #  E#
+/// ##########
+/// ```
+///
+/// La cabecera termina en la primera línea vacía. `facing` está en grados y es
+/// opcional. La posición inicial y la meta pueden darse en la cabecera o con
+/// los glifos 'S' y 'E' dentro de la rejilla; la cabecera tiene prioridad.
+/// La cabecera misma es opcional: si la primera línea no tiene `:`, el archivo
+/// empieza directamente con la rejilla.
+///
+/// 'D' es una puerta normal. Las llaves se colocan con 'r', 'u' e 'y' (roja,
+/// azul y amarilla) y las puertas que abren con la misma letra en mayúscula.
+///
+/// Por defecto el nivel está al aire libre. `ceiling: all` lo techa completo y
+/// `ceiling: x0 y0 x1 y1` techa solo ese rectángulo de celdas (bordes
+/// incluidos); la clave puede repetirse para varias regiones. `sky: cielo.png`
+/// elige el panorama que se ve donde no hay techo; sin ella el cielo es liso.
+/// `fog: linear 2 12 #202830` pone niebla que empieza a 2 y es total a 12;
+/// `fog: exp 0.15 #000000` la pone exponencial con esa densidad.
+///
+/// `ambient: 0.2` fija la luz base del nivel (1 por defecto) y cada
+/// `light: x y radio #RRGGBB [parpadeo]` agrega una luz puntual; el parpadeo
+/// va de 0 (fija) a 1.
+///
+/// Las paredes miden una unidad. `wall_height: M 0.4` cambia la altura de
+/// todas las paredes con ese glifo y `wall_height: 3 2 2.5` la de una sola
+/// celda; si no corresponde a ninguna celda del mapa, el nivel no carga. Las
+/// paredes bajas siguen bloqueando el paso pero se ve por encima.
+impl FromStr for Map {
+    type Err = MapError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut name = String::from("Sin nombre");
+        let mut start = None;
+        let mut direction = 0.0;
+        let mut goal = None;
+        let mut ceiling_all = false;
+        let mut ceiling_regions = Vec::new();
+        let mut sky = None;
+        let mut fog = Fog::off();
+        let mut ambient = 1.0;
+        let mut lights = Vec::new();
+        let mut wall_heights = Vec::new();
+
+        let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line)).peekable();
+
+        // Cabecera: pares `clave: valor` hasta la primera línea vacía. Ningún
+        // glifo es `:`, así que una primera línea sin él ya es la rejilla
+        let has_header = lines.peek().is_some_and(|(_, line)| line.trim_end().is_empty() || line.contains(':'));
+        while let Some((number, line)) = lines.next_if(|_| has_header) {
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            let (key, value) = line.split_once(':').ok_or_else(|| MapError::InvalidHeader {
+                line: number,
+                message: format!("se esperaba `clave: valor`, se encontró {:?}", line),
+            })?;
+            let value = value.trim();
+
+            match key.trim() {
+                "name" => name = value.to_string(),
+                "start" => start = Some(parse_point(number, value)?),
+                "facing" => direction = parse_number(number, value)?.to_radians(),
+                "goal" => goal = Some(parse_point(number, value)?),
+                "sky" => sky = Some(value.to_string()),
+                "fog" => fog = parse_fog(number, value)?,
+                "ambient" => ambient = parse_number(number, value)?.max(0.0),
+                "light" => lights.push(parse_light(number, value)?),
+                "wall_height" => {
+                    let (target, height) = parse_wall_height(number, value)?;
+                    wall_heights.push((number, target, height));
+                }
+                "ceiling" => match value {
+                    "all" => ceiling_all = true,
+                    "none" => ceiling_all = false,
+                    _ => ceiling_regions.push(parse_region(number, value)?),
+                },
+                other => {
+                    return Err(MapError::InvalidHeader {
+                        line: number,
+                        message: format!("clave desconocida {:?}", other),
+                    })
+                }
+            }
+        }
+
+        // Rejilla: el resto de las líneas, ignorando las vacías del final
+        let rows: Vec<(usize, &str)> = lines.collect();
+        let last = rows.iter().rposition(|(_, line)| !line.is_empty());
+        let rows = match last {
+            Some(last) => &rows[..=last],
+            None => return Err(MapError::EmptyGrid),
+        };
+
+        let expected = rows[0].1.chars().count();
+        let mut grid = Vec::with_capacity(rows.len());
+        let mut start_marker = None;
+        let mut goal_marker = None;
+
+        for (y, (number, line)) in rows.iter().enumerate() {
+            let mut row: Vec<char> = line.chars().collect();
+            if row.len() != expected {
+                return Err(MapError::RaggedRow { line: *number, expected, found: row.len() });
+            }
+
+            for (x, glyph) in row.iter_mut().enumerate() {
+                match *glyph {
+                    WALL | BRICK | METAL | EMPTY | DOOR => {}
+                    // Llaves y puertas con llave; `from_grid` las convierte
+                    other if KeyColor::from_key_glyph(other).is_some() => {}
+                    other if KeyColor::from_door_glyph(other).is_some() => {}
+                    START => {
+                        start_marker = Some((x as f64 + 0.5, y as f64 + 0.5));
+                        *glyph = EMPTY;
+                    }
+                    GOAL => goal_marker = Some((x as f64, y as f64)),
+                    other => {
+                        return Err(MapError::UnknownGlyph { line: *number, column: x + 1, glyph: other })
+                    }
+                }
+            }
+
+            grid.push(row);
+        }
+
+        let start = start.or(start_marker).ok_or(MapError::MissingStart)?;
+        let goal = goal.or(goal_marker).ok_or(MapError::MissingGoal)?;
+
+        let mut map = Map::from_grid(name, grid, start, direction, goal);
+        map.sky = sky;
+        map.fog = fog;
+        for (number, target, height) in wall_heights {
+            let mut matched = false;
+            for y in 0..map.height {
+                for x in 0..map.width {
+                    let matches = match target {
+                        HeightTarget::Glyph(glyph) => map.grid[y][x] == glyph,
+                        HeightTarget::Cell(cx, cy) => (cx, cy) == (x, y),
+                    };
+                    if matches {
+                        map.wall_heights[y][x] = height;
+                        matched = true;
+                    }
+                }
+            }
+
+            // Una línea que no toca ninguna celda casi siempre es un error de tipeo
+            if !matched {
+                let message = match target {
+                    HeightTarget::Glyph(glyph) => format!(
+                        "ninguna celda tiene el glifo {:?} (para una sola celda se escribe `<x> <y> <altura>`)",
+                        glyph
+                    ),
+                    HeightTarget::Cell(x, y) => format!("la celda ({}, {}) está fuera del mapa", x, y),
+                };
+                return Err(MapError::InvalidHeader { line: number, message });
+            }
+        }
+        if !lights.is_empty() || ambient != 1.0 {
+            map.lighting = LightMap::build(&map, ambient, lights);
+        }
+        for (y, row) in map.ceiling.iter_mut().enumerate() {
+            for (x, covered) in row.iter_mut().enumerate() {
+                *covered = ceiling_all
+                    || ceiling_regions
+                        .iter()
+                        .any(|&(x0, y0, x1, y1)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y));
+            }
+        }
+        let report = map.validate();
+        if !report.is_ok() {
+            return Err(MapError::Invalid(report));
+        }
+        Ok(map)
+    }
+}
+
+pub fn is_wall_glyph(glyph: char) -> bool {
+    matches!(glyph, WALL | BRICK | METAL)
+}
+
+pub fn is_door_glyph(glyph: char) -> bool {
+    glyph == DOOR || KeyColor::from_door_glyph(glyph).is_some()
+}
+
+/// Centro de la celda que contiene el punto.
+fn cell_center(point: (f64, f64)) -> (f64, f64) {
+    (point.0.floor() + 0.5, point.1.floor() + 0.5)
+}
+
+fn parse_number(line: usize, value: &str) -> Result<f64, MapError> {
+    value.parse().map_err(|_| MapError::InvalidHeader {
+        line,
+        message: format!("{:?} no es un número", value),
+    })
+}
+
+fn parse_point(line: usize, value: &str) -> Result<(f64, f64), MapError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    match parts.as_slice() {
+        [x, y] => Ok((parse_number(line, x)?, parse_number(line, y)?)),
+        _ => Err(MapError::InvalidHeader {
+            line,
+            message: format!("se esperaban dos coordenadas `x y`, se encontró {:?}", value),
+        }),
+    }
+}
+
+/// `none`, `linear <inicio> <fin> <color>` o `exp <densidad> <color>`.
+fn parse_fog(line: usize, value: &str) -> Result<Fog, MapError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let mode = match parts.as_slice() {
+        ["none"] => return Ok(Fog::off()),
+        ["linear", start, end, _] => FogMode::Linear {
+            start: parse_number(line, start)?,
+            end: parse_number(line, end)?,
+        },
+        ["exp", density, _] => FogMode::Exponential { density: parse_number(line, density)? },
+        _ => {
+            return Err(MapError::InvalidHeader {
+                line,
+                message: format!(
+                    "se esperaba `none`, `linear <inicio> <fin> <color>` o `exp <densidad> <color>`, se encontró {:?}",
+                    value
+                ),
+            })
+        }
+    };
+
+    let color = parse_color(line, parts[parts.len() - 1])?;
+    Ok(Fog { mode, color })
+}
+
+/// A qué celdas se aplica una línea `wall_height:`.
+enum HeightTarget {
+    Glyph(char),
+    Cell(usize, usize),
+}
+
+/// `<glifo> <altura>` o `<x> <y> <altura>`.
+fn parse_wall_height(line: usize, value: &str) -> Result<(HeightTarget, f64), MapError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (target, height) = match parts.as_slice() {
+        [glyph, height] if glyph.chars().count() == 1 => {
+            (HeightTarget::Glyph(glyph.chars().next().unwrap()), height)
+        }
+        [x, y, height] => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => (HeightTarget::Cell(x, y), height),
+            _ => {
+                return Err(MapError::InvalidHeader {
+                    line,
+                    message: format!("{:?} no es una celda `x y`", value),
+                })
+            }
+        },
+        _ => {
+            return Err(MapError::InvalidHeader {
+                line,
+                message: format!("se esperaba `<glifo> <altura>` o `<x> <y> <altura>`, se encontró {:?}", value),
+            })
+        }
+    };
+
+    let height = parse_number(line, height)?;
+    if height <= 0.0 {
+        return Err(MapError::InvalidHeader {
+            line,
+            message: format!("la altura debe ser positiva, se encontró {}", height),
+        });
+    }
+    Ok((target, height))
+}
+
+/// `x y radio #RRGGBB [parpadeo]`.
+fn parse_light(line: usize, value: &str) -> Result<Light, MapError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (x, y, radius, color, flicker) = match parts.as_slice() {
+        [x, y, radius, color] => (x, y, radius, color, None),
+        [x, y, radius, color, flicker] => (x, y, radius, color, Some(flicker)),
+        _ => {
+            return Err(MapError::InvalidHeader {
+                line,
+                message: format!("se esperaba `x y radio #RRGGBB [parpadeo]`, se encontró {:?}", value),
+            })
+        }
+    };
+
+    let color = parse_color(line, color)?;
+    let channel = |shift: u32| ((color >> shift) & 0xFF) as f64 / 255.0;
+    let flicker = match flicker {
+        Some(flicker) => parse_number(line, flicker)?.clamp(0.0, 1.0),
+        None => 0.0,
+    };
+
+    Ok(Light {
+        x: parse_number(line, x)?,
+        y: parse_number(line, y)?,
+        radius: parse_number(line, radius)?,
+        color: [channel(16), channel(8), channel(0)],
+        flicker,
+    })
+}
+
+fn parse_color(line: usize, value: &str) -> Result<u32, MapError> {
+    let hex = value.strip_prefix('#').or_else(|| value.strip_prefix("0x"));
+    match hex.map(|hex| u32::from_str_radix(hex, 16)) {
+        Some(Ok(color)) if color <= 0xFFFFFF => Ok(color),
+        _ => Err(MapError::InvalidHeader {
+            line,
+            message: format!("{:?} no es un color `#RRGGBB`", value),
+        }),
+    }
+}
+
+/// Rectángulo de celdas `x0 y0 x1 y1`, con las esquinas en cualquier orden.
+fn parse_region(line: usize, value: &str) -> Result<(usize, usize, usize, usize), MapError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let corners: Vec<usize> = parts.iter().filter_map(|part| part.parse().ok()).collect();
+    match corners.as_slice() {
+        &[x0, y0, x1, y1] if parts.len() == 4 => Ok((x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))),
+        _ => Err(MapError::InvalidHeader {
+            line,
+            message: format!("se esperaba `all`, `none` o una región `x0 y0 x1 y1`, se encontró {:?}", value),
+        }),
+    }
 }
 
 impl Map {
-    pub fn new_level_1() -> Self {
-        let grid = vec![
-            vec!['#', '#', '#', '#', '#', '#', '#', '#', '#', '#'],
-            vec!['#', ' ', ' ', ' ', '#', ' ', ' ', ' ', ' ', '#'],
-            vec!['#', ' ', '#', '#', '#', ' ', '#', ' ', '#', '#'],
-            vec!['#', ' ', ' ', ' ', ' ', ' ', ' ', ' ', ' ', '#'],
-            vec!['#', '#', '#', ' ', '#', '#', ' ', '#', ' ', '#'],
-            vec!['#', ' ', ' ', ' ', '#', ' ', ' ', '#', ' ', '#'],
-            vec!['#', ' ', '#', ' ', '#', '#', ' ', '#', ' ', '#'],
-            vec!['#', ' ', '#', ' ', ' ', ' ', ' ', ' ', ' ', '#'],
-            vec!['#', ' ', ' ', ' ', '#', ' ', ' ', ' ', 'E', '#'],
-            vec!['#', '#', '#', '#', '#', '#', '#', '#', '#', '#'],
-        ];
+    pub fn from_grid(
+        name: String,
+        mut grid: Vec<Vec<char>>,
+        start: (f64, f64),
+        start_direction: f64,
+        goal: (f64, f64),
+    ) -> Self {
+        let (goal_x, goal_y) = cell_center(goal);
+        let mut sprites = vec![Sprite::new(goal_x, goal_y, GOAL)];
 
-        Self {
-            width: grid[0].len(),
-            height: grid.len(),
-            grid,
-            start_x: 1.0,
-            start_y: 1.0,
-            end_x: 8.0,
-            end_y: 8.0,
-        }
-    }
-
-    pub fn new_level_2() -> Self {
-        let grid = vec![
-            vec!['#', '#', '#', '#', '#', '#', '#', '#', '#', '#'],
-            vec!['#', ' ', ' ', ' ', ' ', ' ', ' ', ' ', ' ', '#'],
-            vec!['#', ' ', '#', '#', '#', ' ', '#', '#', ' ', '#'],
-            vec!['#', ' ', '#', ' ', ' ', ' ', '#', ' ', ' ', '#'],
-            vec!['#', ' ', '#', ' ', '#', '#', ' ', '#', ' ', '#'],
-            vec!['#', ' ', ' ', ' ', '#', ' ', ' ', ' ', ' ', '#'],
-            vec!['#', '#', '#', ' ', '#', ' ', '#', '#', '#', '#'],
-            vec!['#', ' ', ' ', ' ', ' ', ' ', ' ', ' ', ' ', '#'],
-            vec!['#', ' ', ' ', ' ', '#', ' ', ' ', ' ', 'E', '#'],
-            vec!['#', '#', '#', '#', '#', '#', '#', '#', '#', '#'],
-        ];
+        // Las llaves pasan a ser sprites y su celda queda libre
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, glyph) in row.iter_mut().enumerate() {
+                if KeyColor::from_key_glyph(*glyph).is_some() {
+                    sprites.push(Sprite::new(x as f64 + 0.5, y as f64 + 0.5, *glyph));
+                    *glyph = EMPTY;
+                }
+            }
+        }
+
+        // Las puertas se orientan según las paredes que las rodean
+        let mut doors = HashMap::new();
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &glyph) in row.iter().enumerate() {
+                if !is_door_glyph(glyph) {
+                    continue;
+                }
+
+                let west = x > 0 && is_wall_glyph(row[x - 1]);
+                let east = row.get(x + 1).is_some_and(|&g| is_wall_glyph(g));
+                let axis = if west && east { DoorAxis::AlongX } else { DoorAxis::AlongY };
+                let door = match KeyColor::from_door_glyph(glyph) {
+                    Some(color) => Door::locked(axis, color),
+                    None => Door::new(axis),
+                };
+                doors.insert((x, y), door);
+            }
+        }
+
+        let width = grid.first().map_or(0, |row| row.len());
+        let height = grid.len();
 
         Self {
-            width: grid[0].len(),
-            height: grid.len(),
+            name,
+            width,
+            height,
+            ceiling: vec![vec![false; width]; height],
+            sky: None,
+            fog: Fog::off(),
+            lighting: LightMap::uniform(width, height),
+            wall_heights: vec![vec![1.0; width]; height],
             grid,
-            start_x: 1.0,
-            start_y: 1.0,
-            end_x: 8.0,
-            end_y: 8.0,
+            start_x: start.0,
+            start_y: start.1,
+            start_direction,
+            end_x: goal.0,
+            end_y: goal.1,
+            sprites,
+            doors,
         }
     }
+
+    /// Mapa mínimo para cuando todavía no hay un nivel cargado, como en el menú.
+    /// No se juega: solo ocupa el lugar del nivel.
+    pub fn placeholder() -> Self {
+        let grid = ["###", "# #", "###"].iter().map(|row| row.chars().collect()).collect();
+        Map::from_grid(String::from("Sin nivel"), grid, (1.5, 1.5), 0.0, (1.0, 1.0))
+    }
+
+    /// Punto de la meta: el centro de su celda. Ahí se dibuja su sprite y ahí
+    /// se mide si el jugador la alcanzó.
+    pub fn goal_position(&self) -> (f64, f64) {
+        cell_center((self.end_x, self.end_y))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        fs::read_to_string(path)?.parse()
+    }
+
     pub fn is_wall(&self, x: f64, y: f64) -> bool {
         let xi = x.floor() as usize;
         let yi = y.floor() as usize;
@@ -67,10 +531,227 @@ impl Map {
             return true;
         }
 
-        self.grid[yi][xi] == '#'
+        is_wall_glyph(self.grid[yi][xi])
+    }
+
+    /// Si la celda que contiene el punto está techada.
+    pub fn has_ceiling(&self, x: f64, y: f64) -> bool {
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+
+        self.ceiling
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Altura de la pared de la celda; fuera del mapa, una unidad.
+    pub fn wall_height(&self, x: i32, y: i32) -> f64 {
+        if x < 0 || y < 0 {
+            return 1.0;
+        }
+
+        self.wall_heights
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// La pared más alta del mapa; un rayo no necesita seguir después de chocar con ella.
+    pub fn max_wall_height(&self) -> f64 {
+        self.wall_heights.iter().flatten().fold(1.0, |max, &height| f64::max(max, height))
+    }
+
+    /// Glifo de la celda, o `None` si está fuera del mapa.
+    pub fn cell(&self, x: i32, y: i32) -> Option<char> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+
+        Some(self.grid[y as usize][x as usize])
+    }
+
+    /// Igual que `is_wall` pero por celda; fuera del mapa todo cuenta como pared.
+    pub fn is_wall_cell(&self, x: i32, y: i32) -> bool {
+        self.cell(x, y).is_none_or(is_wall_glyph)
+    }
+
+    /// Celda que bloquea el movimiento: paredes y puertas que no están abiertas.
+    pub fn is_solid_cell(&self, x: i32, y: i32) -> bool {
+        match self.cell(x, y) {
+            Some(glyph) if is_door_glyph(glyph) => self
+                .doors
+                .get(&(x as usize, y as usize))
+                .is_none_or(|door| !door.is_passable()),
+            Some(glyph) => is_wall_glyph(glyph),
+            None => true,
+        }
+    }
+
+    pub fn door_at(&self, x: i32, y: i32) -> Option<&Door> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.doors.get(&(x as usize, y as usize))
+    }
+
+    /// Abre la puerta en la que está el jugador o la que tiene justo enfrente,
+    /// si no tiene llave o si la llave está en el inventario.
+    pub fn use_door(&mut self, x: f64, y: f64, direction: f64, inventory: &Inventory) -> DoorUse {
+        let ahead_x = x + direction.cos() * USE_REACH;
+        let ahead_y = y + direction.sin() * USE_REACH;
+
+        for (cx, cy) in [(x, y), (ahead_x, ahead_y)] {
+            if cx < 0.0 || cy < 0.0 {
+                continue;
+            }
+            if let Some(door) = self.doors.get_mut(&(cx.floor() as usize, cy.floor() as usize)) {
+                if let Some(color) = door.lock {
+                    if !inventory.has_key(color) {
+                        return DoorUse::Locked(color);
+                    }
+                }
+                door.open();
+                return DoorUse::Opened;
+            }
+        }
+
+        DoorUse::Nothing
+    }
+
+    /// Quita del mapa la llave que el jugador está tocando y devuelve su color.
+    pub fn take_key(&mut self, x: f64, y: f64) -> Option<KeyColor> {
+        let index = self.sprites.iter().position(|sprite| {
+            let dx = sprite.x - x;
+            let dy = sprite.y - y;
+            KeyColor::from_key_glyph(sprite.texture).is_some() && dx * dx + dy * dy < PICKUP_RADIUS * PICKUP_RADIUS
+        })?;
+
+        KeyColor::from_key_glyph(self.sprites.remove(index).texture)
+    }
+
+    /// Anima las puertas. Una puerta no se cierra mientras el círculo del jugador
+    /// la toque.
+    pub fn update_doors(&mut self, delta_time: f64, player_x: f64, player_y: f64, player_radius: f64) {
+        for (&(x, y), door) in self.doors.iter_mut() {
+            let closest_x = player_x.clamp(x as f64, x as f64 + 1.0);
+            let closest_y = player_y.clamp(y as f64, y as f64 + 1.0);
+            let dx = player_x - closest_x;
+            let dy = player_y - closest_y;
+            let occupied = dx * dx + dy * dy < player_radius * player_radius;
+
+            door.update(delta_time, occupied);
+        }
     }
 
     pub fn is_end_position(&self, x: f64, y: f64) ->bool {
         (x.floor() as usize == self.end_x as usize) && (y.floor() as usize == self.end_y as usize)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goal_sprite_sits_on_goal_position() {
+        let map: Map = "start: 1.5 1.5\ngoal: 3 1\n\n#####\n#   #\n#####".parse().unwrap();
+        let sprite = map.sprites.iter().find(|sprite| sprite.texture == GOAL).unwrap();
+
+        assert_eq!(map.goal_position(), (3.5, 1.5));
+        assert_eq!((sprite.x, sprite.y), map.goal_position());
+    }
+
+    #[test]
+    fn grid_without_header_uses_glyphs() {
+        let map: Map = "#####\n#S E#\n#####".parse().unwrap();
+
+        assert_eq!((map.start_x, map.start_y), (1.5, 1.5));
+        assert_eq!((map.end_x, map.end_y), (3.0, 1.0));
+        assert_eq!(map.grid[1][1], EMPTY);
+    }
+
+    #[test]
+    fn header_takes_priority_over_glyphs() {
+        let map: Map = "start: 2.5 1.5\ngoal: 3.2 1.7\n\n#####\n#S E#\n#####".parse().unwrap();
+
+        assert_eq!((map.start_x, map.start_y), (2.5, 1.5));
+        assert_eq!((map.end_x, map.end_y), (3.2, 1.7));
+    }
+
+    #[test]
+    fn ragged_row_reports_its_line() {
+        let result = "name: prueba\n\n#####\n#S E#\n####\n#####".parse::<Map>();
+
+        assert!(
+            matches!(result, Err(MapError::RaggedRow { line: 5, expected: 5, found: 4 })),
+            "{:?}",
+            result.err()
+        );
+    }
+
+    #[test]
+    fn unknown_glyph_reports_line_and_column() {
+        let result = "name: prueba\n\n#####\n#S?E#\n#####".parse::<Map>();
+
+        assert!(
+            matches!(result, Err(MapError::UnknownGlyph { line: 4, column: 3, glyph: '?' })),
+            "{:?}",
+            result.err()
+        );
+    }
+
+    #[test]
+    fn missing_start_is_reported() {
+        let result = "#####\n#  E#\n#####".parse::<Map>();
+
+        assert!(matches!(result, Err(MapError::MissingStart)), "{:?}", result.err());
+    }
+
+    #[test]
+    fn missing_goal_is_reported() {
+        let result = "#####\n#S  #\n#####".parse::<Map>();
+
+        assert!(matches!(result, Err(MapError::MissingGoal)), "{:?}", result.err());
+    }
+
+    #[test]
+    fn wall_height_applies_to_glyph_and_cell() {
+        let map: Map = "wall_height: B 0.4\nwall_height: 0 2 1.8\n\n#####\n#S E#\n#BBB#\n#####".parse().unwrap();
+
+        assert_eq!(map.wall_heights[2][1], 0.4);
+        assert_eq!(map.wall_heights[2][0], 1.8);
+        assert_eq!(map.wall_heights[0][0], 1.0);
+    }
+
+    #[test]
+    fn wall_height_for_missing_glyph_is_rejected() {
+        // Falta la coordenada y: se lee como el glifo '3'
+        let result = "name: prueba\nwall_height: 3 2\n\n#####\n#S E#\n#####".parse::<Map>();
+
+        assert!(matches!(result, Err(MapError::InvalidHeader { line: 2, .. })), "{:?}", result.err());
+    }
+
+    #[test]
+    fn wall_height_outside_the_grid_is_rejected() {
+        let result = "wall_height: 9 1 2\n\n#####\n#S E#\n#####".parse::<Map>();
+
+        assert!(matches!(result, Err(MapError::InvalidHeader { line: 1, .. })), "{:?}", result.err());
+    }
+
+    #[test]
+    fn unknown_header_key_is_rejected() {
+        let result = "name: prueba\nspeed: 3\n\n#####\n#S E#\n#####".parse::<Map>();
+
+        match result {
+            Err(MapError::InvalidHeader { line, message }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("speed"), "{}", message);
+            }
+            other => panic!("se esperaba InvalidHeader, se obtuvo {:?}", other.err()),
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::VecDeque;
+
+use crate::map::Map;
+
+/// Algoritmo con el que se tallan los pasillos.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MazeAlgorithm {
+    /// Búsqueda en profundidad: pasillos largos y sinuosos
+    RecursiveBacktracker,
+    /// Prim aleatorio: muchas ramas cortas
+    Prim,
+    /// Kruskal aleatorio: sin sesgo de dirección
+    Kruskal,
+    /// Eller: fila por fila, con memoria de una sola fila
+    Eller,
+    /// Laberinto perfecto (backtracker) al que luego se le abren callejones
+    /// sin salida con probabilidad `loop_chance` para formar ciclos
+    Braid { loop_chance: f64 },
+}
+
+impl MazeAlgorithm {
+    /// Nombre corto usado en el manifiesto de niveles.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "backtracker" => Some(MazeAlgorithm::RecursiveBacktracker),
+            "prim" => Some(MazeAlgorithm::Prim),
+            "kruskal" => Some(MazeAlgorithm::Kruskal),
+            "eller" => Some(MazeAlgorithm::Eller),
+            "braid" => Some(MazeAlgorithm::Braid { loop_chance: 0.5 }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MazeAlgorithm::RecursiveBacktracker => "backtracker",
+            MazeAlgorithm::Prim => "prim",
+            MazeAlgorithm::Kruskal => "kruskal",
+            MazeAlgorithm::Eller => "eller",
+            MazeAlgorithm::Braid { .. } => "braid",
+        }
+    }
+}
+
+/// Generador pseudoaleatorio SplitMix64: pequeño, rápido y reproducible a
+/// partir de la semilla, que es lo único que importa aquí.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Entero en [0, bound)
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Número en [0, 1)
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Rejilla de celdas del laberinto. La celda `(x, y)` ocupa la casilla
+/// `(2x + 1, 2y + 1)` del mapa y las paredes entre celdas quedan en medio.
+struct Carver {
+    cells_x: usize,
+    cells_y: usize,
+    grid: Vec<Vec<char>>,
+}
+
+impl Carver {
+    fn new(cells_x: usize, cells_y: usize) -> Self {
+        Self {
+            cells_x,
+            cells_y,
+            grid: vec![vec!['#'; cells_x * 2 + 1]; cells_y * 2 + 1],
+        }
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.cells_x + x
+    }
+
+    fn open_cell(&mut self, x: usize, y: usize) {
+        self.grid[y * 2 + 1][x * 2 + 1] = ' ';
+    }
+
+    /// Abre la celda `b` y la pared que la separa de la celda vecina `a`.
+    fn connect(&mut self, a: (usize, usize), b: (usize, usize)) {
+        self.open_cell(a.0, a.1);
+        self.open_cell(b.0, b.1);
+        self.grid[a.1 + b.1 + 1][a.0 + b.0 + 1] = ' ';
+    }
+
+    fn is_connected(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        self.grid[a.1 + b.1 + 1][a.0 + b.0 + 1] == ' '
+    }
+
+    fn neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut result = Vec::with_capacity(4);
+        if x > 0 {
+            result.push((x - 1, y));
+        }
+        if x + 1 < self.cells_x {
+            result.push((x + 1, y));
+        }
+        if y > 0 {
+            result.push((x, y - 1));
+        }
+        if y + 1 < self.cells_y {
+            result.push((x, y + 1));
+        }
+        result
+    }
+}
+
+/// Genera un nivel de `cells_x` por `cells_y` celdas (el mapa mide `2n + 1`
+/// casillas por lado). La misma semilla y el mismo algoritmo producen siempre
+/// el mismo mapa. El inicio y la meta se colocan en los extremos del camino más
+/// largo del laberinto.
+pub fn generate(cells_x: usize, cells_y: usize, algorithm: MazeAlgorithm, seed: u64) -> Map {
+    let cells_x = cells_x.max(2);
+    let cells_y = cells_y.max(2);
+    let mut rng = Rng::new(seed);
+    let mut carver = Carver::new(cells_x, cells_y);
+
+    match algorithm {
+        MazeAlgorithm::RecursiveBacktracker => recursive_backtracker(&mut carver, &mut rng),
+        MazeAlgorithm::Prim => prim(&mut carver, &mut rng),
+        MazeAlgorithm::Kruskal => kruskal(&mut carver, &mut rng),
+        MazeAlgorithm::Eller => eller(&mut carver, &mut rng),
+        MazeAlgorithm::Braid { loop_chance } => {
+            recursive_backtracker(&mut carver, &mut rng);
+            braid(&mut carver, &mut rng, loop_chance);
+        }
+    }
+
+    // Extremos del camino más largo: lo más lejano a una celda cualquiera, y lo
+    // más lejano a eso
+    let start = farthest_cell(&carver, (0, 0));
+    let goal = farthest_cell(&carver, start);
+
+    // Mirar hacia el primer pasillo abierto desde el inicio
+    let facing = carver
+        .neighbors(start.0, start.1)
+        .into_iter()
+        .find(|&next| carver.is_connected(start, next))
+        .map_or(0.0, |(x, y)| (y as f64 - start.1 as f64).atan2(x as f64 - start.0 as f64));
+
+    let mut grid = carver.grid;
+    let (goal_x, goal_y) = (goal.0 * 2 + 1, goal.1 * 2 + 1);
+    grid[goal_y][goal_x] = 'E';
+
+    Map::from_grid(
+        format!("Laberinto {} #{}", algorithm.name(), seed),
+        grid,
+        ((start.0 * 2 + 1) as f64 + 0.5, (start.1 * 2 + 1) as f64 + 0.5),
+        facing,
+        (goal_x as f64, goal_y as f64),
+    )
+}
+
+fn recursive_backtracker(carver: &mut Carver, rng: &mut Rng) {
+    let mut visited = vec![false; carver.cells_x * carver.cells_y];
+    let start = (rng.below(carver.cells_x), rng.below(carver.cells_y));
+    let mut stack = vec![start];
+    visited[carver.index(start.0, start.1)] = true;
+    carver.open_cell(start.0, start.1);
+
+    // Pila explícita en lugar de recursión para no desbordar en mapas grandes
+    while let Some(&current) = stack.last() {
+        let unvisited: Vec<(usize, usize)> = carver
+            .neighbors(current.0, current.1)
+            .into_iter()
+            .filter(|&(x, y)| !visited[carver.index(x, y)])
+            .collect();
+
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = unvisited[rng.below(unvisited.len())];
+        visited[carver.index(next.0, next.1)] = true;
+        carver.connect(current, next);
+        stack.push(next);
+    }
+}
+
+fn prim(carver: &mut Carver, rng: &mut Rng) {
+    let mut in_maze = vec![false; carver.cells_x * carver.cells_y];
+    let start = (rng.below(carver.cells_x), rng.below(carver.cells_y));
+    in_maze[carver.index(start.0, start.1)] = true;
+    carver.open_cell(start.0, start.1);
+
+    // Frontera: pares (celda del laberinto, vecina todavía fuera)
+    let mut frontier: Vec<((usize, usize), (usize, usize))> =
+        carver.neighbors(start.0, start.1).into_iter().map(|next| (start, next)).collect();
+
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.below(frontier.len()));
+        if in_maze[carver.index(to.0, to.1)] {
+            continue;
+        }
+
+        in_maze[carver.index(to.0, to.1)] = true;
+        carver.connect(from, to);
+        for next in carver.neighbors(to.0, to.1) {
+            if !in_maze[carver.index(next.0, next.1)] {
+                frontier.push((to, next));
+            }
+        }
+    }
+}
+
+fn kruskal(carver: &mut Carver, rng: &mut Rng) {
+    let mut sets = DisjointSet::new(carver.cells_x * carver.cells_y);
+
+    let mut edges = Vec::new();
+    for y in 0..carver.cells_y {
+        for x in 0..carver.cells_x {
+            if x + 1 < carver.cells_x {
+                edges.push(((x, y), (x + 1, y)));
+            }
+            if y + 1 < carver.cells_y {
+                edges.push(((x, y), (x, y + 1)));
+            }
+        }
+    }
+    rng.shuffle(&mut edges);
+
+    for (a, b) in edges {
+        if sets.union(carver.index(a.0, a.1), carver.index(b.0, b.1)) {
+            carver.connect(a, b);
+        }
+    }
+}
+
+fn eller(carver: &mut Carver, rng: &mut Rng) {
+    let width = carver.cells_x;
+    let mut next_set = 0;
+    let mut row_sets: Vec<usize> = Vec::new();
+
+    for y in 0..carver.cells_y {
+        let last_row = y + 1 == carver.cells_y;
+
+        // Las celdas que no bajaron de la fila anterior reciben un conjunto nuevo
+        for x in 0..width {
+            if row_sets.len() <= x {
+                row_sets.push(usize::MAX);
+            }
+            if row_sets[x] == usize::MAX {
+                row_sets[x] = next_set;
+                next_set += 1;
+            }
+            carver.open_cell(x, y);
+        }
+
+        // Unir vecinos horizontales de conjuntos distintos (todos en la última fila)
+        for x in 0..width - 1 {
+            if row_sets[x] != row_sets[x + 1] && (last_row || rng.below(2) == 0) {
+                carver.connect((x, y), (x + 1, y));
+                let (keep, replace) = (row_sets[x], row_sets[x + 1]);
+                for set in row_sets.iter_mut() {
+                    if *set == replace {
+                        *set = keep;
+                    }
+                }
+            }
+        }
+
+        if last_row {
+            break;
+        }
+
+        // Cada conjunto baja al menos una vez a la fila siguiente
+        let mut below = vec![usize::MAX; width];
+        let mut members: Vec<usize> = (0..width).collect();
+        rng.shuffle(&mut members);
+        let mut has_descent: Vec<usize> = Vec::new();
+        for &x in &members {
+            if rng.below(2) == 0 {
+                carver.connect((x, y), (x, y + 1));
+                below[x] = row_sets[x];
+                has_descent.push(row_sets[x]);
+            }
+        }
+        for &x in &members {
+            if !has_descent.contains(&row_sets[x]) {
+                carver.connect((x, y), (x, y + 1));
+                below[x] = row_sets[x];
+                has_descent.push(row_sets[x]);
+            }
+        }
+
+        row_sets = below;
+    }
+}
+
+/// Abre callejones sin salida derribando una pared hacia otra celda, lo que crea ciclos.
+fn braid(carver: &mut Carver, rng: &mut Rng, loop_chance: f64) {
+    let mut cells: Vec<(usize, usize)> = (0..carver.cells_y)
+        .flat_map(|y| (0..carver.cells_x).map(move |x| (x, y)))
+        .collect();
+    rng.shuffle(&mut cells);
+
+    for cell in cells {
+        let neighbors = carver.neighbors(cell.0, cell.1);
+        let open = neighbors.iter().filter(|&&next| carver.is_connected(cell, next)).count();
+        if open != 1 || rng.unit() >= loop_chance {
+            continue;
+        }
+
+        let closed: Vec<(usize, usize)> = neighbors
+            .into_iter()
+            .filter(|&next| !carver.is_connected(cell, next))
+            .collect();
+        if !closed.is_empty() {
+            carver.connect(cell, closed[rng.below(closed.len())]);
+        }
+    }
+}
+
+/// Celda más lejana (en pasos) desde `from`, recorriendo solo pasillos abiertos.
+fn farthest_cell(carver: &Carver, from: (usize, usize)) -> (usize, usize) {
+    let mut distance = vec![usize::MAX; carver.cells_x * carver.cells_y];
+    let mut queue = VecDeque::new();
+    distance[carver.index(from.0, from.1)] = 0;
+    queue.push_back(from);
+    let mut farthest = from;
+
+    while let Some(current) = queue.pop_front() {
+        farthest = current;
+        let steps = distance[carver.index(current.0, current.1)];
+        for next in carver.neighbors(current.0, current.1) {
+            let index = carver.index(next.0, next.1);
+            if distance[index] == usize::MAX && carver.is_connected(current, next) {
+                distance[index] = steps + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    farthest
+}
+
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        Self { parent: (0..size).collect() }
+    }
+
+    fn find(&mut self, mut item: usize) -> usize {
+        while self.parent[item] != item {
+            self.parent[item] = self.parent[self.parent[item]];
+            item = self.parent[item];
+        }
+        item
+    }
+
+    /// Une los conjuntos de `a` y `b`; devuelve `false` si ya eran el mismo.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        self.parent[root_b] = root_a;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [MazeAlgorithm; 5] = [
+        MazeAlgorithm::RecursiveBacktracker,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::Braid { loop_chance: 0.5 },
+    ];
+
+    fn assert_same_maze(algorithm: MazeAlgorithm) {
+        for seed in [0, 1, 42, u64::MAX] {
+            let first = generate(12, 9, algorithm, seed);
+            let second = generate(12, 9, algorithm, seed);
+            assert_eq!(first.grid, second.grid, "{} #{}", algorithm.name(), seed);
+            assert_eq!((first.start_x, first.start_y), (second.start_x, second.start_y));
+            assert_eq!((first.end_x, first.end_y), (second.end_x, second.end_y));
+            assert_eq!(first.start_direction, second.start_direction);
+        }
+
+        // Otra semilla da otro laberinto
+        assert_ne!(generate(12, 9, algorithm, 1).grid, generate(12, 9, algorithm, 2).grid, "{}", algorithm.name());
+    }
+
+    #[test]
+    fn backtracker_is_deterministic() {
+        assert_same_maze(MazeAlgorithm::RecursiveBacktracker);
+    }
+
+    #[test]
+    fn prim_is_deterministic() {
+        assert_same_maze(MazeAlgorithm::Prim);
+    }
+
+    #[test]
+    fn kruskal_is_deterministic() {
+        assert_same_maze(MazeAlgorithm::Kruskal);
+    }
+
+    #[test]
+    fn eller_is_deterministic() {
+        assert_same_maze(MazeAlgorithm::Eller);
+    }
+
+    #[test]
+    fn braid_is_deterministic() {
+        assert_same_maze(MazeAlgorithm::Braid { loop_chance: 0.5 });
+    }
+
+    #[test]
+    fn generated_mazes_pass_validation() {
+        for algorithm in ALGORITHMS {
+            for (cells_x, cells_y) in [(2, 2), (5, 3), (12, 9), (30, 30)] {
+                for seed in 0..20 {
+                    let map = generate(cells_x, cells_y, algorithm, seed);
+                    let report = map.validate();
+                    assert!(
+                        report.is_ok(),
+                        "{} {}x{} #{}: {}",
+                        algorithm.name(),
+                        cells_x,
+                        cells_y,
+                        seed,
+                        report
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::inventory::KeyColor;
+use crate::map::Map;
+use crate::player::Player;
+use crate::raycaster::Occluder;
+
+// Distancia del minimapa a la esquina superior izquierda de la pantalla
+const MARGIN: usize = 4;
+// Límites del zoom, en píxeles por celda
+const MIN_ZOOM: f64 = 2.0;
+const MAX_ZOOM: f64 = 16.0;
+// Largo del cono de visión, en píxeles del minimapa
+const CONE_LENGTH: f64 = 40.0;
+// Paso con el que se recorre cada rayo al marcar celdas vistas, en celdas
+const REVEAL_STEP: f64 = 0.1;
+
+const WALL_COLOR: u32 = 0xFFFFFF;
+const FLOOR_COLOR: u32 = 0x000000;
+const DOOR_COLOR: u32 = 0x8B4513;
+const GOAL_COLOR: u32 = 0x00A000;
+const ITEM_COLOR: u32 = 0x60FF60;
+const HINT_COLOR: u32 = 0xFFD700;
+const PLAYER_COLOR: u32 = 0xFF0000;
+const CONE_COLOR: u32 = 0xFFE070;
+const UNSEEN_COLOR: u32 = 0x303030;
+const OUTSIDE_COLOR: u32 = 0x181818;
+const BORDER_COLOR: u32 = 0x808080;
+
+/// Cómo se marca hacia dónde mira el jugador.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heading {
+    Arrow,
+    /// El campo de visión de la cámara
+    Cone,
+}
+
+/// Minimapa cuadrado en la esquina superior izquierda, centrado en el jugador.
+///
+/// Puede quedarse con el norte (la fila 0 del mapa) arriba o girar con el
+/// jugador para que su dirección apunte siempre hacia arriba. Con
+/// `fog_of_war` solo muestra las celdas que alcanzaron los rayos de la cámara
+/// en algún cuadro desde que empezó el nivel.
+pub struct Minimap {
+    /// Girar con el jugador en lugar de dejar el norte arriba
+    pub rotate: bool,
+    /// Píxeles por celda
+    pub zoom: f64,
+    /// Lado del cuadro en pantalla, en píxeles
+    pub size: usize,
+    /// Cuánto se corrió la vista desde el jugador, en celdas y en los ejes del
+    /// propio minimapa (x a la derecha, y hacia abajo)
+    pub scroll: (f64, f64),
+    pub heading: Heading,
+    pub fog_of_war: bool,
+    width: usize,
+    height: usize,
+    seen: Vec<bool>,
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Self {
+            rotate: false,
+            zoom: 4.0,
+            size: 96,
+            scroll: (0.0, 0.0),
+            heading: Heading::Arrow,
+            fog_of_war: true,
+            width: 0,
+            height: 0,
+            seen: Vec::new(),
+        }
+    }
+
+    /// Olvida lo explorado; se llama al empezar cada nivel.
+    pub fn reset(&mut self, map: &Map) {
+        self.width = map.width;
+        self.height = map.height;
+        self.seen = vec![false; map.width * map.height];
+        self.scroll = (0.0, 0.0);
+    }
+
+    pub fn zoom_by(&mut self, factor: f64) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    pub fn scroll_by(&mut self, dx: f64, dy: f64) {
+        self.scroll.0 += dx;
+        self.scroll.1 += dy;
+    }
+
+    pub fn center(&mut self) {
+        self.scroll = (0.0, 0.0);
+    }
+
+    pub fn is_seen(&self, x: usize, y: usize) -> bool {
+        !self.fog_of_war || self.seen.get(y * self.width + x).copied().unwrap_or(false)
+    }
+
+    /// Marca como vistas las celdas por las que pasó cada rayo de `cast_rays`,
+    /// desde la cámara hasta la pared más lejana que dibujó en su columna
+    /// (la primera de `z_buffer`, que se guarda de atrás hacia adelante).
+    pub fn reveal(&mut self, map: &Map, camera: &Camera, z_buffer: &[Vec<Occluder>]) {
+        if self.width != map.width || self.height != map.height {
+            self.reset(map);
+        }
+
+        let columns = z_buffer.len();
+        for (x, occluders) in z_buffer.iter().enumerate() {
+            let farthest = match occluders.first() {
+                Some(occluder) => occluder.distance,
+                None => continue,
+            };
+
+            let camera_x = 2.0 * (x as f64) / (columns as f64) - 1.0;
+            let (ray_x, ray_y) = camera.ray_direction(camera_x);
+            // `distance` es perpendicular: cada unidad recorre `length` celdas sobre el rayo
+            let length = (ray_x * ray_x + ray_y * ray_y).sqrt();
+            let step = REVEAL_STEP / length;
+
+            // Un paso de más para entrar en la celda de la pared
+            let mut t = 0.0;
+            while t <= farthest + step {
+                let cell_x = camera.x + ray_x * t;
+                let cell_y = camera.y + ray_y * t;
+                if cell_x >= 0.0 && cell_y >= 0.0 && (cell_x as usize) < self.width && (cell_y as usize) < self.height {
+                    self.seen[cell_y as usize * self.width + cell_x as usize] = true;
+                }
+                t += step;
+            }
+        }
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, map: &Map, player: &Player, hint_route: &[(usize, usize)]) {
+        // Marcas por celda: ruta de ayuda y objetos (llaves y la meta)
+        let mut route = vec![false; map.width * map.height];
+        for &(x, y) in hint_route {
+            if x < map.width && y < map.height {
+                route[y * map.width + x] = true;
+            }
+        }
+        let mut items = vec![None; map.width * map.height];
+        for sprite in &map.sprites {
+            let (x, y) = (sprite.x as usize, sprite.y as usize);
+            if x < map.width && y < map.height {
+                let color = KeyColor::from_key_glyph(sprite.texture).map_or(ITEM_COLOR, |color| color.rgb());
+                items[y * map.width + x] = Some(color);
+            }
+        }
+
+        let (sin, cos) = player.direction.sin_cos();
+        // Ejes del jugador vistos en el minimapa: hacia adelante y hacia su derecha
+        let (forward, right) = if self.rotate {
+            ((0.0, -1.0), (1.0, 0.0))
+        } else {
+            ((cos, sin), (-sin, cos))
+        };
+        let half_fov = player.fov / 2.0;
+        let half = self.size as f64 / 2.0;
+
+        for py in 0..self.size {
+            for px in 0..self.size {
+                // Punto del minimapa relativo al jugador, en celdas
+                let local_x = (px as f64 + 0.5 - half) / self.zoom + self.scroll.0;
+                let local_y = (py as f64 + 0.5 - half) / self.zoom + self.scroll.1;
+                let (world_x, world_y) = if self.rotate {
+                    (
+                        player.x - local_x * sin - local_y * cos,
+                        player.y + local_x * cos - local_y * sin,
+                    )
+                } else {
+                    (player.x + local_x, player.y + local_y)
+                };
+
+                let mut color = self.cell_color(map, world_x, world_y, &route, &items);
+
+                // Posición respecto al jugador en píxeles, a lo largo de su dirección y a su derecha
+                let ahead = (local_x * forward.0 + local_y * forward.1) * self.zoom;
+                let aside = (local_x * right.0 + local_y * right.1) * self.zoom;
+                match self.heading {
+                    Heading::Arrow => {
+                        if (-4.0..=6.0).contains(&ahead) && aside.abs() <= (6.0 - ahead) * 0.45 {
+                            color = PLAYER_COLOR;
+                        }
+                    }
+                    Heading::Cone => {
+                        let reach = ahead * ahead + aside * aside;
+                        if ahead > 0.0 && reach <= CONE_LENGTH * CONE_LENGTH && aside.atan2(ahead).abs() <= half_fov {
+                            color = blend(color, CONE_COLOR);
+                        }
+                        if reach <= 4.0 {
+                            color = PLAYER_COLOR;
+                        }
+                    }
+                }
+
+                if px == 0 || py == 0 || px == self.size - 1 || py == self.size - 1 {
+                    color = BORDER_COLOR;
+                }
+                framebuffer.point(MARGIN + px, MARGIN + py, color);
+            }
+        }
+    }
+
+    // Color del minimapa en el punto del mundo (x, y)
+    fn cell_color(&self, map: &Map, x: f64, y: f64, route: &[bool], items: &[Option<u32>]) -> u32 {
+        if x < 0.0 || y < 0.0 || x as usize >= map.width || y as usize >= map.height {
+            return OUTSIDE_COLOR;
+        }
+        let (cell_x, cell_y) = (x as usize, y as usize);
+        let index = cell_y * map.width + cell_x;
+        let seen = self.is_seen(cell_x, cell_y);
+
+        // Las marcas ocupan la mitad central de la celda
+        let centered = (x.fract() - 0.5).abs() < 0.25 && (y.fract() - 0.5).abs() < 0.25;
+        if centered {
+            if let (true, Some(color)) = (seen, items[index]) {
+                return color;
+            }
+            // La ayuda se ve aunque la celda no se haya explorado
+            if route[index] {
+                return HINT_COLOR;
+            }
+        }
+
+        if !seen {
+            return UNSEEN_COLOR;
+        }
+        if map.is_wall(x, y) {
+            WALL_COLOR
+        } else if map.is_solid_cell(cell_x as i32, cell_y as i32) {
+            // Puertas cerradas, del color de su llave si la tienen
+            match map.door_at(cell_x as i32, cell_y as i32).and_then(|door| door.lock) {
+                Some(color) => color.rgb(),
+                None => DOOR_COLOR,
+            }
+        } else if map.is_end_position(x, y) {
+            GOAL_COLOR
+        } else {
+            FLOOR_COLOR
+        }
+    }
+}
+
+// Mitad y mitad de cada canal
+fn blend(a: u32, b: u32) -> u32 {
+    ((a >> 1) & 0x7F7F7F) + ((b >> 1) & 0x7F7F7F)
+}
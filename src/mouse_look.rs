@@ -0,0 +1,62 @@
+use minifb::{MouseMode, Window};
+
+/// Girar la cámara con el mouse. minifb no puede fijar el cursor al centro de la
+/// ventana, así que "capturar" oculta el cursor y usa el desplazamiento entre
+/// cuadros; al salir de la ventana el giro se detiene hasta que el cursor vuelva.
+pub struct MouseLook {
+    pub captured: bool,
+    /// Radianes de giro por píxel de desplazamiento del mouse
+    pub sensitivity: f64,
+    /// Invertir el eje horizontal
+    pub invert_x: bool,
+    /// Invertir el eje vertical
+    pub invert_y: bool,
+    last_position: Option<(f32, f32)>,
+}
+
+impl MouseLook {
+    pub fn new() -> Self {
+        Self {
+            captured: false,
+            sensitivity: 0.004,
+            invert_x: false,
+            invert_y: false,
+            last_position: None,
+        }
+    }
+
+    pub fn set_captured(&mut self, window: &mut Window, captured: bool) {
+        self.captured = captured;
+        self.last_position = None;
+        window.set_cursor_visibility(!captured);
+    }
+
+    pub fn toggle(&mut self, window: &mut Window) {
+        let captured = !self.captured;
+        self.set_captured(window, captured);
+    }
+
+    /// Giro horizontal y vertical en radianes desde el cuadro anterior
+    /// (positivos hacia la derecha y hacia arriba).
+    pub fn update(&mut self, window: &mut Window) -> (f64, f64) {
+        if !self.captured || !window.is_active() {
+            self.last_position = None;
+            return (0.0, 0.0);
+        }
+
+        let position = window.get_mouse_pos(MouseMode::Pass);
+        let (delta_x, delta_y) = match (self.last_position, position) {
+            (Some((last_x, last_y)), Some((x, y))) => ((x - last_x) as f64, (y - last_y) as f64),
+            _ => (0.0, 0.0),
+        };
+        self.last_position = position;
+
+        let sign_x = if self.invert_x { -1.0 } else { 1.0 };
+        // En pantalla la y crece hacia abajo: subir el mouse mira hacia arriba
+        let sign_y = if self.invert_y { 1.0 } else { -1.0 };
+        (
+            delta_x * self.sensitivity * sign_x,
+            delta_y * self.sensitivity * sign_y,
+        )
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::inventory::KeyColor;
+use crate::map::Map;
+
+/// Ruta más corta con A* entre dos celdas, moviéndose en cuatro direcciones.
+/// Las puertas con llave solo se cruzan si su color está en `keys`. Incluye la
+/// celda de origen y la de destino; `None` si no hay camino.
+pub fn shortest_path(
+    map: &Map,
+    from: (usize, usize),
+    to: (usize, usize),
+    keys: &[KeyColor],
+) -> Option<Vec<(usize, usize)>> {
+    let walkable = |x: usize, y: usize| {
+        let lock = map.doors.get(&(x, y)).and_then(|door| door.lock);
+        !map.is_wall_cell(x as i32, y as i32) && lock.is_none_or(|color| keys.contains(&color))
+    };
+    if !walkable(from.0, from.1) || !walkable(to.0, to.1) {
+        return None;
+    }
+
+    let index = |x: usize, y: usize| y * map.width + x;
+    // Distancia Manhattan: nunca sobreestima en una rejilla de cuatro direcciones
+    let heuristic = |x: usize, y: usize| x.abs_diff(to.0) + y.abs_diff(to.1);
+
+    let mut cost = vec![usize::MAX; map.width * map.height];
+    let mut came_from = vec![usize::MAX; map.width * map.height];
+    let mut open = BinaryHeap::new();
+
+    cost[index(from.0, from.1)] = 0;
+    open.push(Reverse((heuristic(from.0, from.1), 0, from)));
+
+    while let Some(Reverse((_, steps, (x, y)))) = open.pop() {
+        if (x, y) == to {
+            break;
+        }
+        // Entrada vieja: ya se encontró un camino más corto a esta celda
+        if steps > cost[index(x, y)] {
+            continue;
+        }
+
+        let neighbors = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+        for (nx, ny) in neighbors {
+            if nx >= map.width || ny >= map.height || !walkable(nx, ny) {
+                continue;
+            }
+
+            let next_steps = steps + 1;
+            if next_steps < cost[index(nx, ny)] {
+                cost[index(nx, ny)] = next_steps;
+                came_from[index(nx, ny)] = index(x, y);
+                open.push(Reverse((next_steps + heuristic(nx, ny), next_steps, (nx, ny))));
+            }
+        }
+    }
+
+    if cost[index(to.0, to.1)] == usize::MAX {
+        return None;
+    }
+
+    // Reconstruir desde el destino hacia atrás
+    let mut path = vec![to];
+    let mut current = index(to.0, to.1);
+    while current != index(from.0, from.1) {
+        current = came_from[current];
+        path.push((current % map.width, current / map.width));
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Ruta desde una posición del mundo hasta la meta del mapa llevando las
+/// llaves `keys`. Si hace falta, la ruta pasa antes por las llaves que abren
+/// el camino, en el orden que la haga más corta.
+pub fn route_to_goal(map: &Map, x: f64, y: f64, keys: &[KeyColor]) -> Option<Vec<(usize, usize)>> {
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+
+    let from = (x.floor() as usize, y.floor() as usize);
+    let to = (map.end_x.floor() as usize, map.end_y.floor() as usize);
+    if from.0 >= map.width || from.1 >= map.height || to.0 >= map.width || to.1 >= map.height {
+        return None;
+    }
+
+    route_collecting_keys(map, from, to, &mut keys.to_vec())
+}
+
+/// Ruta más corta de `from` a `to` que puede desviarse a recoger las llaves que
+/// siguen en el mapa. Prueba cada llave alcanzable como siguiente parada; con
+/// tres colores son pocas combinaciones.
+fn route_collecting_keys(
+    map: &Map,
+    from: (usize, usize),
+    to: (usize, usize),
+    keys: &mut Vec<KeyColor>,
+) -> Option<Vec<(usize, usize)>> {
+    let mut best = shortest_path(map, from, to, keys);
+
+    for sprite in &map.sprites {
+        let color = match KeyColor::from_key_glyph(sprite.texture) {
+            Some(color) if !keys.contains(&color) => color,
+            _ => continue,
+        };
+        let key_cell = (sprite.x.floor() as usize, sprite.y.floor() as usize);
+        let Some(to_key) = shortest_path(map, from, key_cell, keys) else {
+            continue;
+        };
+
+        keys.push(color);
+        let rest = route_collecting_keys(map, key_cell, to, keys);
+        keys.pop();
+
+        if let Some(rest) = rest {
+            // La celda de la llave aparece al final de un tramo y al principio del otro
+            if best.as_ref().is_none_or(|best| to_key.len() + rest.len() - 1 < best.len()) {
+                let mut route = to_key;
+                route.extend_from_slice(&rest[1..]);
+                best = Some(route);
+            }
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(rows: &[&str]) -> Map {
+        let grid = rows.iter().map(|row| row.chars().collect()).collect();
+        Map::from_grid(String::from("prueba"), grid, (1.5, 1.5), 0.0, (5.0, 1.0))
+    }
+
+    #[test]
+    fn locked_door_blocks_path_without_its_key() {
+        let map = map(&["#######", "#  R  #", "#######"]);
+
+        assert_eq!(shortest_path(&map, (1, 1), (5, 1), &[]), None);
+        assert_eq!(shortest_path(&map, (1, 1), (5, 1), &[KeyColor::Red]).map(|path| path.len()), Some(5));
+    }
+
+    #[test]
+    fn route_fetches_key_before_locked_door() {
+        // La llave roja está en un callejón abajo del inicio
+        let map = map(&["#######", "#  R  #", "# #####", "#r#####", "#######"]);
+
+        let route = route_to_goal(&map, 1.5, 1.5, &[]).unwrap();
+
+        assert_eq!(route, vec![(1, 1), (1, 2), (1, 3), (1, 2), (1, 1), (2, 1), (3, 1), (4, 1), (5, 1)]);
+    }
+
+    #[test]
+    fn route_skips_keys_already_held() {
+        let map = map(&["#######", "#  R  #", "# #####", "#r#####", "#######"]);
+
+        let route = route_to_goal(&map, 1.5, 1.5, &[KeyColor::Red]).unwrap();
+
+        assert_eq!(route.len(), 5);
+    }
+
+    #[test]
+    fn route_collects_keys_in_order() {
+        // La llave amarilla está detrás de la puerta roja y la meta detrás de la amarilla
+        let map = map(&["#########", "#r R yY #", "#########"]);
+        let map = Map { end_x: 7.0, ..map };
+
+        let route = route_to_goal(&map, 2.5, 1.5, &[]).unwrap();
+
+        assert_eq!(route, vec![(2, 1), (1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1), (7, 1)]);
+    }
+
+    #[test]
+    fn unreachable_key_means_no_route() {
+        let map = map(&["#######", "# R r #", "#######"]);
+
+        assert_eq!(route_to_goal(&map, 1.5, 1.5, &[]), None);
+    }
+}
@@ -1,9 +1,64 @@
+use crate::collision;
+use crate::inventory::Inventory;
 use crate::map::Map;
+
+// Nunca integrar pasos más largos que esto, aunque un cuadro tarde mucho
+const MAX_DELTA_TIME: f64 = 0.1;
+// Cuánto se puede mirar hacia arriba o hacia abajo, en radianes
+const MAX_PITCH: f64 = 0.4;
+// Velocidad de inclinación con el teclado (radianes/s)
+const LOOK_SPEED: f64 = 1.0;
+// Altura de los ojos de pie y agachado, en unidades de pared
+const STAND_HEIGHT: f64 = 0.5;
+const CROUCH_HEIGHT: f64 = 0.3;
+// Velocidad con la que se agacha o se levanta (unidades/s)
+const CROUCH_SPEED: f64 = 1.5;
+// Velocidad inicial del salto (unidades/s) y gravedad (unidades/s²)
+const JUMP_SPEED: f64 = 1.6;
+const GRAVITY: f64 = 6.0;
+
 pub struct Player {
     pub x: f64,
     pub y: f64,
     pub direction: f64,
+    /// Inclinación de la vista en radianes, positiva hacia arriba
+    pub pitch: f64,
+    /// Altura actual de los ojos sobre el piso: la de la postura más la del salto
+    pub eye_height: f64,
     pub fov: f64,
+    /// Velocidad actual en unidades del mapa por segundo
+    pub velocity_x: f64,
+    pub velocity_y: f64,
+    pub walk_speed: f64,
+    pub run_speed: f64,
+    /// Cuánto cambia la velocidad por segundo mientras hay entrada (unidades/s²)
+    pub acceleration: f64,
+    /// Frenado por segundo cuando se suelta la entrada (unidades/s²)
+    pub friction: f64,
+    /// Velocidad de giro con teclado en radianes por segundo
+    pub turn_speed: f64,
+    /// Radio del círculo con el que el jugador choca contra las paredes
+    pub radius: f64,
+    pub inventory: Inventory,
+    input: MovementInput,
+    // Altura de los ojos por la postura (de pie o agachado) y por el salto
+    stance_height: f64,
+    jump_height: f64,
+    vertical_velocity: f64,
+}
+
+/// Intención de movimiento del cuadro actual, cada eje en [-1, 1].
+#[derive(Clone, Copy, Default)]
+pub struct MovementInput {
+    pub forward: f64,
+    pub strafe: f64,
+    pub turn: f64,
+    /// Inclinar la vista con el teclado, positivo hacia arriba
+    pub look: f64,
+    pub running: bool,
+    pub crouching: bool,
+    /// Empezar un salto en este cuadro, si el jugador está en el piso
+    pub jump: bool,
 }
 
 impl Player {
@@ -12,49 +67,141 @@ impl Player {
             x,
             y,
             direction,
+            pitch: 0.0,
+            eye_height: STAND_HEIGHT,
             fov: 60.0_f64.to_radians(),
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            walk_speed: 2.5,
+            run_speed: 4.5,
+            acceleration: 20.0,
+            friction: 15.0,
+            turn_speed: 3.0,
+            radius: 0.25,
+            inventory: Inventory::new(),
+            input: MovementInput::default(),
+            stance_height: STAND_HEIGHT,
+            jump_height: 0.0,
+            vertical_velocity: 0.0,
         }
     }
 
-    pub fn move_forward(&mut self, distance: f64, map: &Map) {
-        let speed = 0.5;
-        let new_x = self.x + self.direction.cos() * distance * speed;
-        let new_y = self.y + self.direction.sin() * distance * speed;
-        let min_distance_to_wall = 0.5;
-
-        if !map.is_wall(new_x, self.y) && !map.is_wall(new_x + min_distance_to_wall, self.y) {
-            self.x = new_x;
-        }
-        if !map.is_wall(self.x, new_y) && !map.is_wall(self.x, new_y + min_distance_to_wall) {
-            self.y = new_y;
-        }
+    /// Coloca al jugador quieto en una nueva posición, por ejemplo al iniciar un nivel.
+    pub fn place(&mut self, x: f64, y: f64, direction: f64) {
+        self.x = x;
+        self.y = y;
+        self.direction = direction;
+        self.pitch = 0.0;
+        self.velocity_x = 0.0;
+        self.velocity_y = 0.0;
+        self.input = MovementInput::default();
+        self.stance_height = STAND_HEIGHT;
+        self.jump_height = 0.0;
+        self.vertical_velocity = 0.0;
+        self.eye_height = STAND_HEIGHT;
     }
 
-    pub fn move_backward(&mut self, distance: f64, map: &Map) {
-        let speed = 0.5;
-        let new_x = self.x - self.direction.cos() * distance * speed;
-        let new_y = self.y - self.direction.sin() * distance * speed;
-        let min_distance_to_wall = 0.5;
+    /// Guarda la entrada que `update` va a integrar en el siguiente paso.
+    pub fn set_input(&mut self, input: MovementInput) {
+        self.input = input;
+    }
 
-        if !map.is_wall(new_x, self.y) && !map.is_wall(new_x + min_distance_to_wall, self.y) {
-            self.x = new_x;
-        }
-        if !map.is_wall(self.x, new_y) && !map.is_wall(self.x, new_y + min_distance_to_wall) {
-            self.y = new_y;
-        }
+    pub fn turn_right(&mut self, angle: f64) {
+        self.direction += angle;
     }
 
-    pub fn turn_left(&mut self, angle: f64) {
-        self.direction -= angle;
+    /// Inclina la vista hacia arriba (ángulo positivo) o hacia abajo, con tope.
+    pub fn look_up(&mut self, angle: f64) {
+        self.pitch = (self.pitch + angle).clamp(-MAX_PITCH, MAX_PITCH);
     }
 
-    pub fn turn_right(&mut self, angle: f64) {
-        self.direction += angle;
+    pub fn center_view(&mut self) {
+        self.pitch = 0.0;
     }
 
     pub fn update(&mut self, map: &Map, delta_time: f64) {
-        let _ = delta_time;
-        let _ = map;
-        // Lógica de actualización si es necesario
+        let delta_time = delta_time.clamp(0.0, MAX_DELTA_TIME);
+        let input = self.input;
+
+        self.turn_right(input.turn * self.turn_speed * delta_time);
+        self.look_up(input.look * LOOK_SPEED * delta_time);
+
+        // Dirección deseada: adelante más lateral (derecha positiva), normalizada
+        let (sin, cos) = self.direction.sin_cos();
+        let mut wish_x = cos * input.forward - sin * input.strafe;
+        let mut wish_y = sin * input.forward + cos * input.strafe;
+        let wish_length = (wish_x * wish_x + wish_y * wish_y).sqrt();
+        if wish_length > 1.0 {
+            wish_x /= wish_length;
+            wish_y /= wish_length;
+        }
+
+        // Agachado se camina más lento y no se corre
+        let speed = match (input.crouching, input.running) {
+            (true, _) => self.walk_speed * 0.5,
+            (false, true) => self.run_speed,
+            (false, false) => self.walk_speed,
+        };
+        let (target_x, target_y, rate) = if wish_length > 0.0 {
+            (wish_x * speed, wish_y * speed, self.acceleration)
+        } else {
+            (0.0, 0.0, self.friction)
+        };
+
+        // Acercar la velocidad al objetivo sin pasarse, a lo sumo `rate * dt` por paso
+        let change_x = target_x - self.velocity_x;
+        let change_y = target_y - self.velocity_y;
+        let change = (change_x * change_x + change_y * change_y).sqrt();
+        let max_change = rate * delta_time;
+        if change <= max_change {
+            self.velocity_x = target_x;
+            self.velocity_y = target_y;
+        } else {
+            self.velocity_x += change_x / change * max_change;
+            self.velocity_y += change_y / change * max_change;
+        }
+
+        if delta_time <= 0.0 {
+            return;
+        }
+
+        self.update_height(input, delta_time);
+
+        let (new_x, new_y) = collision::move_circle(
+            map,
+            self.x,
+            self.y,
+            self.velocity_x * delta_time,
+            self.velocity_y * delta_time,
+            self.radius,
+        );
+
+        // Quedarse con la velocidad que realmente se logró: al chocar se pierde
+        // la componente contra la pared y queda la que desliza a lo largo de ella
+        self.velocity_x = (new_x - self.x) / delta_time;
+        self.velocity_y = (new_y - self.y) / delta_time;
+        self.x = new_x;
+        self.y = new_y;
+    }
+
+    /// Agacharse, levantarse y saltar: mueve la altura de los ojos.
+    fn update_height(&mut self, input: MovementInput, delta_time: f64) {
+        let target = if input.crouching { CROUCH_HEIGHT } else { STAND_HEIGHT };
+        let step = CROUCH_SPEED * delta_time;
+        self.stance_height += (target - self.stance_height).clamp(-step, step);
+
+        let on_ground = self.jump_height <= 0.0;
+        if input.jump && on_ground && !input.crouching {
+            self.vertical_velocity = JUMP_SPEED;
+        }
+
+        self.vertical_velocity -= GRAVITY * delta_time;
+        self.jump_height += self.vertical_velocity * delta_time;
+        if self.jump_height <= 0.0 {
+            self.jump_height = 0.0;
+            self.vertical_velocity = 0.0;
+        }
+
+        self.eye_height = self.stance_height + self.jump_height;
     }
 }
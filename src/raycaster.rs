@@ -1,87 +1,492 @@
+use crate::camera::Camera;
+use crate::door::{Door, DoorAxis};
+use crate::fog::{self, SIDE_SHADE};
+use crate::lighting;
 use crate::map::Map;
 use crate::player::Player;
 use crate::framebuffer::Framebuffer;
-use image::{DynamicImage, GenericImageView};
+use crate::textures::TextureRegistry;
 
-pub struct Raycaster;
+// Distancia máxima que recorre un rayo antes de rendirse
+const MAX_DISTANCE: f64 = 100.0;
+
+/// Cara de la celda contra la que chocó el rayo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    West,
+    East,
+    North,
+    South,
+}
+
+/// Resultado de lanzar un rayo por la rejilla.
+pub struct RayHit {
+    /// Distancia perpendicular al plano de la cámara (ya sin ojo de pez)
+    pub distance: f64,
+    /// 0 si chocó con una cara vertical (eje x), 1 si fue horizontal (eje y)
+    pub side: i32,
+    pub face: Face,
+    pub hit_x: f64,
+    pub hit_y: f64,
+    pub map_x: i32,
+    pub map_y: i32,
+    /// Distancia a la que el rayo sale de la celda; con la de entrada da el
+    /// largo de la parte de arriba de una pared baja
+    pub exit_distance: f64,
+    /// Desplazamiento de la textura a lo largo de la cara; en las puertas es lo
+    /// que la hoja ya se corrió dentro de la pared
+    pub texture_offset: f64,
+}
+
+/// Pared que tapa una columna desde `top` (fila de pantalla) hacia abajo, a
+/// `distance` de la cámara. Los sprites más lejanos no se dibujan ahí.
+#[derive(Clone, Copy, Debug)]
+pub struct Occluder {
+    pub distance: f64,
+    pub top: usize,
+}
+
+pub struct Raycaster {
+    /// Hilos entre los que se reparten las columnas en `cast_rays`
+    pub threads: usize,
+}
+
+/// Franja vertical de columnas `[left, left + width)` de la pantalla que dibuja
+/// un hilo por su cuenta antes de copiarla de vuelta al framebuffer. Escribe
+/// directo en su tramo del `z_buffer`, reutilizando las listas de cada columna.
+struct Strip<'a> {
+    left: usize,
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+    depths: &'a mut [Vec<Occluder>],
+    // Choques del rayo de la columna actual; se reutiliza entre columnas
+    hits: Vec<RayHit>,
+}
+
+impl<'a> Strip<'a> {
+    fn copy_from(framebuffer: &Framebuffer, left: usize, depths: &'a mut [Vec<Occluder>]) -> Self {
+        let width = depths.len();
+        let mut pixels = Vec::with_capacity(width * framebuffer.height);
+        for y in 0..framebuffer.height {
+            let row = y * framebuffer.width + left;
+            pixels.extend_from_slice(&framebuffer.buffer[row..row + width]);
+        }
+
+        Self {
+            left,
+            width,
+            height: framebuffer.height,
+            pixels,
+            depths,
+            hits: Vec::new(),
+        }
+    }
+
+    fn point(&mut self, x: usize, y: usize, color: u32) {
+        self.pixels[y * self.width + x - self.left] = color;
+    }
+}
 
 impl Raycaster {
+    /// Usa `RAYCASTER_THREADS` si está definida; si no, un hilo por núcleo.
     pub fn new() -> Self {
-        Raycaster
+        let threads = std::env::var("RAYCASTER_THREADS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |count| count.get()));
+        Self::with_threads(threads)
+    }
+
+    pub fn with_threads(threads: usize) -> Self {
+        Self { threads: threads.max(1) }
     }
 
-    pub fn cast_rays(&self, map: &Map, player: &Player, framebuffer: &mut Framebuffer, wall_texture: &DynamicImage) {
-        for x in 0..framebuffer.width {
-            let camera_x = 2.0 * (x as f64) / (framebuffer.width as f64) - 1.0;
-            let ray_dir = player.direction + player.fov / 2.0 * camera_x;
-    
-            // Cast ray and get correct perpendicular distance
-            let (mut distance, hit_side, hit_x, hit_y) = self.cast_ray(map, player, ray_dir);
-    
-            // Avoid too small distances that can cause distortion
-            if distance < 0.01 {
-                distance = 0.01;
+    /// Dibuja las paredes y deja en `z_buffer` las paredes que tapan cada
+    /// columna, para que los sprites sepan qué queda oculto.
+    ///
+    /// Las columnas no dependen unas de otras, así que se reparten en franjas
+    /// entre `threads` hilos. Cada columna se calcula igual sin importar el
+    /// hilo, por lo que el resultado es idéntico al de un solo hilo.
+    pub fn cast_rays(
+        &self,
+        map: &Map,
+        player: &Player,
+        framebuffer: &mut Framebuffer,
+        z_buffer: &mut [Vec<Occluder>],
+        wall_textures: &TextureRegistry,
+    ) {
+        let camera = Camera::from_player(player);
+        let max_height = map.max_wall_height();
+        let width = framebuffer.width;
+        let threads = self.threads.clamp(1, width.max(1));
+        let strip_width = width.div_ceil(threads);
+
+        // Cada franja devuelve sus píxeles; sus columnas del z_buffer ya quedan escritas
+        let source: &Framebuffer = framebuffer;
+        let draw_strip = |left: usize, depths: &mut [Vec<Occluder>]| {
+            let mut strip = Strip::copy_from(source, left, depths);
+            for x in left..left + strip.width {
+                self.draw_column(map, &camera, wall_textures, width, max_height, x, &mut strip);
             }
-    
-            // Ensure distance is perpendicular, adjusting calculation based on hit_side
-            if hit_side == 0 {
-                distance *= (ray_dir - player.direction).cos(); // Adjust perpendicular distance
+            (left, strip.width, strip.pixels)
+        };
+
+        let z_buffer = &mut z_buffer[..width];
+        let strips: Vec<(usize, usize, Vec<u32>)> = if threads == 1 {
+            vec![draw_strip(0, z_buffer)]
+        } else {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = z_buffer
+                    .chunks_mut(strip_width)
+                    .enumerate()
+                    .map(|(index, depths)| scope.spawn(move || draw_strip(index * strip_width, depths)))
+                    .collect();
+                handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+            })
+        };
+
+        for (left, strip_width, pixels) in strips {
+            for y in 0..framebuffer.height {
+                let row = y * framebuffer.width + left;
+                framebuffer.buffer[row..row + strip_width].copy_from_slice(&pixels[y * strip_width..(y + 1) * strip_width]);
             }
-    
-            let wall_height = (framebuffer.height as f64 / distance) as usize;
-            let start = (framebuffer.height / 2).saturating_sub(wall_height / 2);
-            let end = (framebuffer.height / 2) + wall_height / 2;
-    
-            // Calculate exact position in texture
-            let texture_x = if hit_side == 0 {
-                (hit_y % 1.0 * wall_texture.width() as f64) as u32
-            } else {
-                (hit_x % 1.0 * wall_texture.width() as f64) as u32
-            };
-    
-            for y in start..end {
-                let texture_y = ((y as f64 - start as f64) / wall_height as f64 * wall_texture.height() as f64) as u32;
-                let color = wall_texture.get_pixel(texture_x, texture_y).0;
-                let color = ((color[0] as u32) << 16) | ((color[1] as u32) << 8) | (color[2] as u32);
-                framebuffer.point(x, y, color);
+        }
+    }
+
+    /// Lanza el rayo de la columna `x` y dibuja sus paredes dentro de la franja.
+    /// El rayo sigue de largo detrás de las paredes por encima de las cuales
+    /// todavía puede asomar algo: se detiene en una pared tan alta como la más
+    /// alta del mapa o cuya parte de arriba ya sale por el borde de la
+    /// pantalla. Después se dibujan de atrás hacia adelante, así lo que asoma
+    /// por encima de una pared baja queda a la vista.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_column(
+        &self,
+        map: &Map,
+        camera: &Camera,
+        wall_textures: &TextureRegistry,
+        screen_width: usize,
+        max_height: f64,
+        x: usize,
+        strip: &mut Strip,
+    ) {
+        let camera_x = 2.0 * (x as f64) / (screen_width as f64) - 1.0;
+        let (ray_x, ray_y) = camera.ray_direction(camera_x);
+
+        let horizon = camera.horizon(strip.height);
+        let screen_height = strip.height as f64;
+
+        let mut hits = std::mem::take(&mut strip.hits);
+        hits.clear();
+        self.march(map, camera.x, camera.y, ray_x, ray_y, |hit| {
+            // Las paredes más lejanas se ven más chicas: si esta ya tapa hasta
+            // arriba de la pantalla, detrás no asoma nada
+            let wall_height = map.wall_height(hit.map_x, hit.map_y);
+            let top = horizon - (wall_height - camera.eye_height) * screen_height / hit.distance;
+            let done = wall_height >= max_height || top <= 0.0;
+            hits.push(hit);
+            !done
+        });
+
+        let mut occluders = std::mem::take(&mut strip.depths[x - strip.left]);
+        occluders.clear();
+        for hit in hits.iter().rev() {
+            if let Some(occluder) = self.draw_wall(map, camera, wall_textures, x, hit, strip) {
+                occluders.push(occluder);
             }
         }
+        strip.depths[x - strip.left] = occluders;
+        strip.hits = hits;
     }
 
-    fn cast_ray(
+    /// Dibuja la cara de pared de un choque (y su parte de arriba si la pared
+    /// queda por debajo de los ojos). Devuelve lo que esa pared tapa.
+    fn draw_wall(
         &self,
         map: &Map,
-        player: &Player,
-        ray_dir: f64,
-    ) -> (f64, i32, f64, f64) {
-        let mut distance = 0.0;
-        let mut step_size = 0.05;
-        let mut hit_side = 0; // 0 for vertical, 1 for horizontal
-        let mut current_x = player.x;
-        let mut current_y = player.y;
-
-        while !map.is_wall(current_x, current_y) && distance < 100.0 {
-            let next_x = current_x + ray_dir.cos() * step_size;
-            let next_y = current_y + ray_dir.sin() * step_size;
-
-            if (next_x as usize) != (current_x as usize) {
-                hit_side = 0;
-            } else if (next_y as usize) != (current_y as usize) {
-                hit_side = 1;
+        camera: &Camera,
+        wall_textures: &TextureRegistry,
+        x: usize,
+        hit: &RayHit,
+        strip: &mut Strip,
+    ) -> Option<Occluder> {
+        // La textura depende del glifo de la celda que se golpeó
+        let glyph = map.cell(hit.map_x, hit.map_y).unwrap_or('#');
+        let wall_texture = wall_textures.get(glyph)?;
+        let light = map.lighting.at_face(hit);
+        let lit = !map.lighting.is_full_bright();
+        let wall_height = map.wall_height(hit.map_x, hit.map_y);
+
+        // Avoid too small distances that can cause distortion
+        let distance = hit.distance.max(0.01);
+
+        // Una unidad de altura mide `height / distance` píxeles, medidos desde el horizonte
+        let horizon = camera.horizon(strip.height);
+        let eye_height = camera.eye_height;
+        let unit = strip.height as f64 / distance;
+        let top = horizon - (wall_height - eye_height) * unit;
+        let bottom = horizon + eye_height * unit;
+        let start = top.max(0.0) as usize;
+        let end = (bottom.max(0.0) as usize).min(strip.height);
+
+        // Las paredes lejanas usan un mipmap más chico para que no parpadeen
+        let wall_texture_full = wall_texture;
+        let wall_texture = wall_texture.mip(wall_texture.height as f64 / unit);
+        let texture_width = wall_texture.width;
+        let texture_height = wall_texture.height;
+
+        // Calculate exact position in texture
+        let wall_x = if hit.side == 0 { hit.hit_y } else { hit.hit_x };
+        let wall_x = wall_x - wall_x.floor() - hit.texture_offset;
+        let mut texture_x = ((wall_x * texture_width as f64) as u32).min(texture_width - 1);
+
+        // Voltear la textura en las caras opuestas para que no se vea en espejo
+        if hit.face == Face::East || hit.face == Face::North {
+            texture_x = texture_width - 1 - texture_x;
+        }
+
+        let shade = |mut color: u32, distance: f64| {
+            // Caras horizontales más oscuras para distinguir los dos ejes
+            if hit.side == 1 {
+                color = fog::shade(color, SIDE_SHADE);
+            }
+            if lit {
+                color = lighting::modulate(color, light);
+            }
+            map.fog.apply(color, distance)
+        };
+
+        for y in start..end {
+            // La textura se repite cada unidad de altura, alineada con el borde de arriba
+            let below_top = (y as f64 - top) / unit;
+            let texture_y = (below_top.rem_euclid(1.0) * texture_height as f64) as u32;
+            let texture_y = texture_y.min(texture_height - 1);
+            let color = wall_texture.get(texture_x, texture_y) & 0xFFFFFF;
+            strip.point(x, y, shade(color, hit.distance));
+        }
+
+        // Parte de arriba de una pared más baja que los ojos: va desde el borde
+        // de arriba de la cara hasta donde el rayo sale de la celda
+        let mut occluded_from = start;
+        if wall_height < eye_height && hit.exit_distance > hit.distance {
+            let far_top = horizon + (eye_height - wall_height) * strip.height as f64 / hit.exit_distance;
+            let cap_start = far_top.max(0.0) as usize;
+            let cap_end = (top.max(0.0) as usize).min(strip.height);
+            // El mipmap de 1×1 es el color promedio de la textura
+            let cap_color = fog::shade(wall_texture_full.mip(f64::INFINITY).get(0, 0) & 0xFFFFFF, 0.85);
+            for y in cap_start..cap_end {
+                strip.point(x, y, shade(cap_color, hit.distance));
             }
+            occluded_from = occluded_from.min(cap_start);
+        }
+
+        Some(Occluder {
+            distance: hit.distance,
+            top: occluded_from,
+        })
+    }
+
+    /// Recorre la rejilla con DDA desde `(origin_x, origin_y)`, saltando de
+    /// borde de celda en borde de celda hasta encontrar una pared.
+    pub fn cast_ray(&self, map: &Map, origin_x: f64, origin_y: f64, ray_x: f64, ray_y: f64) -> Option<RayHit> {
+        let mut first = None;
+        self.march(map, origin_x, origin_y, ray_x, ray_y, |hit| {
+            first = Some(hit);
+            false
+        });
+        first
+    }
+
+    /// Como `cast_ray`, pero entrega cada pared que cruza el rayo a `visit`, de
+    /// la más cercana a la más lejana, mientras `visit` devuelva `true`. Siempre
+    /// se detiene en el borde del mapa.
+    pub fn march(
+        &self,
+        map: &Map,
+        origin_x: f64,
+        origin_y: f64,
+        ray_x: f64,
+        ray_y: f64,
+        mut visit: impl FnMut(RayHit) -> bool,
+    ) {
+        let mut map_x = origin_x.floor() as i32;
+        let mut map_y = origin_y.floor() as i32;
+
+        // Distancia a recorrer sobre el rayo para cruzar una celda completa en cada eje
+        let delta_x = if ray_x == 0.0 { f64::INFINITY } else { (1.0 / ray_x).abs() };
+        let delta_y = if ray_y == 0.0 { f64::INFINITY } else { (1.0 / ray_y).abs() };
 
-            current_x = next_x;
-            current_y = next_y;
-            distance += step_size;
+        let (step_x, mut side_dist_x) = if ray_x < 0.0 {
+            (-1, (origin_x - map_x as f64) * delta_x)
+        } else {
+            (1, (map_x as f64 + 1.0 - origin_x) * delta_x)
+        };
+        let (step_y, mut side_dist_y) = if ray_y < 0.0 {
+            (-1, (origin_y - map_y as f64) * delta_y)
+        } else {
+            (1, (map_y as f64 + 1.0 - origin_y) * delta_y)
+        };
 
-            // Adjust step size dynamically based on distance
-            if distance > 50.0 {
-                step_size = 0.1;
-            } else if distance < 5.0 {
-                step_size = 0.01; // High precision for short distances
+        loop {
+            let side = if side_dist_x < side_dist_y {
+                side_dist_x += delta_x;
+                map_x += step_x;
+                0
+            } else {
+                side_dist_y += delta_y;
+                map_y += step_y;
+                1
+            };
+
+            let distance = if side == 0 { side_dist_x - delta_x } else { side_dist_y - delta_y };
+            if distance > MAX_DISTANCE {
+                return;
+            }
+
+            // Las puertas se dibujan como una hoja delgada en medio de la celda;
+            // el rayo sale de la celda en la distancia del siguiente borde
+            if let Some(door) = map.door_at(map_x, map_y) {
+                let exit = side_dist_x.min(side_dist_y);
+                let origin = (origin_x, origin_y);
+                if let Some(hit) = door_hit(door, origin, ray_x, ray_y, map_x, map_y, distance, exit) {
+                    if !visit(hit) {
+                        return;
+                    }
+                }
+                continue;
+            }
+
+            let outside = map.cell(map_x, map_y).is_none();
+            if map.is_wall_cell(map_x, map_y) {
+                let face = match (side, step_x, step_y) {
+                    (0, 1, _) => Face::West,
+                    (0, _, _) => Face::East,
+                    (_, _, 1) => Face::North,
+                    _ => Face::South,
+                };
+
+                let hit = RayHit {
+                    distance,
+                    side,
+                    face,
+                    hit_x: origin_x + ray_x * distance,
+                    hit_y: origin_y + ray_y * distance,
+                    map_x,
+                    map_y,
+                    exit_distance: side_dist_x.min(side_dist_y),
+                    texture_offset: 0.0,
+                };
+                if !visit(hit) || outside {
+                    return;
+                }
             }
         }
+    }
+}
 
-        (distance, hit_side, current_x, current_y)
+/// Choque del rayo con la hoja de una puerta, si ocurre mientras el rayo está
+/// dentro de la celda (entre `enter` y `exit`) y en la parte no abierta.
+#[allow(clippy::too_many_arguments)]
+fn door_hit(
+    door: &Door,
+    origin: (f64, f64),
+    ray_x: f64,
+    ray_y: f64,
+    map_x: i32,
+    map_y: i32,
+    enter: f64,
+    exit: f64,
+) -> Option<RayHit> {
+    let (distance, side, face) = match door.axis {
+        DoorAxis::AlongY => {
+            if ray_x == 0.0 {
+                return None;
+            }
+            let face = if ray_x > 0.0 { Face::West } else { Face::East };
+            ((map_x as f64 + 0.5 - origin.0) / ray_x, 0, face)
+        }
+        DoorAxis::AlongX => {
+            if ray_y == 0.0 {
+                return None;
+            }
+            let face = if ray_y > 0.0 { Face::North } else { Face::South };
+            ((map_y as f64 + 0.5 - origin.1) / ray_y, 1, face)
+        }
+    };
+
+    if distance < enter || distance > exit {
+        return None;
+    }
+
+    let hit_x = origin.0 + ray_x * distance;
+    let hit_y = origin.1 + ray_y * distance;
+
+    // La hoja se corre hacia el lado positivo del eje; lo que quedó atrás está abierto
+    let along = if side == 0 { hit_y - map_y as f64 } else { hit_x - map_x as f64 };
+    if along < door.open_amount {
+        return None;
+    }
+
+    Some(RayHit {
+        distance,
+        side,
+        face,
+        hit_x,
+        hit_y,
+        map_x,
+        map_y,
+        exit_distance: distance,
+        texture_offset: door.open_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::Texture;
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    fn textures() -> TextureRegistry {
+        let mut textures = TextureRegistry::mipmapped('#');
+        let image = RgbaImage::from_fn(16, 16, |x, y| Rgba([(x * 16) as u8, (y * 16) as u8, ((x ^ y) * 16) as u8, 255]));
+        textures.insert('#', Texture::from_image_mipmapped(&DynamicImage::ImageRgba8(image)));
+        textures
+    }
+
+    fn map() -> Map {
+        let rows = ["##########", "#        #", "#  B  D  #", "#        #", "# M    # #", "#        #", "##########"];
+        let grid = rows.iter().map(|row| row.chars().collect()).collect();
+        let mut map = Map::from_grid(String::from("prueba"), grid, (1.5, 1.5), 0.0, (8.0, 5.0));
+        // Una pared baja, una alta y una puerta a medio abrir
+        map.wall_heights[4][2] = 0.4;
+        map.wall_heights[2][3] = 1.8;
+        map.doors.get_mut(&(6, 2)).unwrap().open_amount = 0.5;
+        map
+    }
+
+    fn render(threads: usize, player: &Player) -> (Vec<u32>, Vec<Vec<(f64, usize)>>) {
+        let (width, height) = (203, 121);
+        let mut framebuffer = Framebuffer::new(width, height);
+        let mut z_buffer = vec![Vec::new(); width];
+        Raycaster::with_threads(threads).cast_rays(&map(), player, &mut framebuffer, &mut z_buffer, &textures());
+
+        let depths = z_buffer
+            .iter()
+            .map(|column| column.iter().map(|occluder| (occluder.distance, occluder.top)).collect())
+            .collect();
+        (framebuffer.buffer, depths)
+    }
+
+    #[test]
+    fn output_does_not_depend_on_thread_count() {
+        let mut player = Player::new(1.5, 5.2, -0.6);
+        player.look_up(0.15);
+
+        let single = render(1, &player);
+        assert!(single.0.iter().any(|&color| color != 0), "no se dibujó ninguna pared");
+        for threads in [2, 3, 7, 16, 500] {
+            assert!(render(threads, &player) == single, "{} hilos dan otra imagen", threads);
+        }
     }
-}
\ No newline at end of file
+}
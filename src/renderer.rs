@@ -1,143 +1,231 @@
-use image::{DynamicImage, GenericImageView};
+use std::path::{Path, PathBuf};
 
+use image::ImageResult;
+
+use crate::camera::Camera;
 use crate::framebuffer::Framebuffer;
+use crate::inventory::KeyColor;
+use crate::lighting;
 use crate::map::Map;
+use crate::minimap::Minimap;
 use crate::player::Player;
-use crate::raycaster::Raycaster;
+use crate::raycaster::{Occluder, Raycaster};
+use crate::sprite;
+use crate::textures::{Texture, TextureRegistry};
+
+// Alto de la franja del HUD al pie de la pantalla y lado de cada casilla de llave
+const HUD_HEIGHT: usize = 28;
+const HUD_SLOT: usize = 18;
 
 pub struct Renderer {
     pub framebuffer: Framebuffer,
     pub raycaster: Raycaster,
-    pub wall_texture: DynamicImage,
-    pub floor_texture: DynamicImage,
+    pub wall_textures: TextureRegistry,
+    pub sprite_textures: TextureRegistry,
+    pub z_buffer: Vec<Vec<Occluder>>,
+    pub floor_texture: Texture,
+    pub ceiling_texture: Texture,
+    /// Panorama de 360° del nivel actual; sin él se usa `sky_color`
+    pub sky_texture: Option<Texture>,
     pub sky_color: u32,
+    /// Celdas de la ruta de ayuda que se marcan en el minimapa (vacío = sin ayuda)
+    pub hint_route: Vec<(usize, usize)>,
+    pub minimap: Minimap,
+    assets: PathBuf,
+}
+
+/// Carpeta de las texturas: `RAYCASTER_ASSETS` si está definida, si no la
+/// carpeta `src` del proyecto, sin depender del directorio de trabajo.
+pub fn assets_dir() -> PathBuf {
+    match std::env::var_os("RAYCASTER_ASSETS") {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+    }
 }
 
 impl Renderer {
     pub fn new(width: usize, height: usize) -> Self {
-        let wall_texture = image::open("src/pared.png").unwrap();
-        let floor_texture = image::open("src/tierra.png").unwrap();
+        Self::with_assets(width, height, &assets_dir()).unwrap()
+    }
+
+    pub fn with_assets(width: usize, height: usize, assets: &Path) -> ImageResult<Self> {
+        let mut wall_textures = TextureRegistry::mipmapped('#');
+        wall_textures.load('#', assets.join("pared.png"))?;
+        wall_textures.load('B', assets.join("ladrillo.png"))?;
+        wall_textures.load('M', assets.join("metal.png"))?;
+        wall_textures.load('D', assets.join("puerta.png"))?;
+        let mut sprite_textures = TextureRegistry::new('E');
+        sprite_textures.load('E', assets.join("meta.png"))?;
+        for color in KeyColor::ALL {
+            wall_textures.load(color.door_glyph(), assets.join(format!("puerta_{}.png", color.name())))?;
+            sprite_textures.load(color.key_glyph(), assets.join(format!("llave_{}.png", color.name())))?;
+        }
+        let floor_texture = Texture::load(assets.join("tierra.png"))?;
+        let ceiling_texture = Texture::load(assets.join("techo.png"))?;
         let sky_color = 0x87CEEB; // Color azul cielo
 
-        Self {
+        Ok(Self {
             framebuffer: Framebuffer::new(width, height),
             raycaster: Raycaster::new(),
-            wall_texture,
+            wall_textures,
+            sprite_textures,
+            z_buffer: vec![Vec::new(); width],
             floor_texture,
+            ceiling_texture,
+            sky_texture: None,
             sky_color,
-        }
+            hint_route: Vec::new(),
+            minimap: Minimap::new(),
+            assets: assets.to_path_buf(),
+        })
+    }
+
+    /// Carga el panorama del cielo de un nivel (`Map::sky`), o lo quita con `None`.
+    pub fn set_sky(&mut self, file: Option<&str>) -> ImageResult<()> {
+        self.sky_texture = match file {
+            Some(file) => Some(Texture::load(self.assets.join(file))?),
+            None => None,
+        };
+        Ok(())
     }
 
     pub fn render_scene(&mut self, map: &Map, player: &Player) {
         self.framebuffer.clear(self.sky_color);
-        self.render_floor(player);
-        self.raycaster.cast_rays(map, player, &mut self.framebuffer, &self.wall_texture);
-        self.render_goal_3d(map, player);
-        self.render_minimap(map, player);
+        self.render_sky(player);
+        self.render_ceiling(map, player);
+        self.render_floor(map, player);
+        self.raycaster.cast_rays(map, player, &mut self.framebuffer, &mut self.z_buffer, &self.wall_textures);
+        let camera = Camera::from_player(player);
+        sprite::render_sprites(
+            &map.sprites,
+            &camera,
+            &mut self.framebuffer,
+            &self.z_buffer,
+            &self.sprite_textures,
+            &map.fog,
+        );
+        self.minimap.reveal(map, &camera, &self.z_buffer);
+        self.minimap.render(&mut self.framebuffer, map, player, &self.hint_route);
+        self.render_hud(player);
     }
 
-    fn render_goal_3d(&mut self, map: &Map, player: &Player) {
-        let goal_x = map.end_x;
-        let goal_y = map.end_y;
-    
-        let dx = goal_x - player.x;
-        let dy = goal_y - player.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-    
-        if distance < 0.1 {
-            return;
+    /// Franja inferior con una casilla por color de llave, rellena si el
+    /// jugador la lleva.
+    fn render_hud(&mut self, player: &Player) {
+        let width = self.framebuffer.width;
+        let height = self.framebuffer.height;
+        let top = height.saturating_sub(HUD_HEIGHT);
+
+        for y in top..height {
+            for x in 0..width {
+                self.framebuffer.point(x, y, 0x202020);
+            }
         }
-    
-        let angle_to_goal = (dy).atan2(dx) - player.direction;
-    
-        if angle_to_goal.abs() < player.fov / 2.0 {
-            let screen_x = (self.framebuffer.width as f64 / 2.0) * (1.0 + angle_to_goal / player.fov);
-            let goal_height = (self.framebuffer.height as f64 / distance) as usize;
-            let start_y = (self.framebuffer.height / 2).saturating_sub(goal_height / 2); // Evitar desbordamientos
-            let end_y = start_y + goal_height;
-    
-            if screen_x >= 0.0 && screen_x < self.framebuffer.width as f64 {
-                let start_x = (screen_x as usize).saturating_sub(goal_height / 2); // Evita el desbordamiento
-                let end_x = (screen_x as usize).saturating_add(goal_height / 2); // Asegura que no haya desbordamiento al sumar
-            
-                for y in start_y..end_y {
-                    for x in start_x..end_x {
-                        if x < self.framebuffer.width && y < self.framebuffer.height {
-                            self.framebuffer.point(x, y, 0x00FF00); // Color verde para la meta
-                        }
+
+        let slot_top = top + (HUD_HEIGHT - HUD_SLOT) / 2;
+        for (i, color) in KeyColor::ALL.into_iter().enumerate() {
+            let slot_left = 8 + i * (HUD_SLOT + 6);
+            let held = player.inventory.has_key(color);
+
+            for py in 0..HUD_SLOT {
+                for px in 0..HUD_SLOT {
+                    let border = px == 0 || py == 0 || px == HUD_SLOT - 1 || py == HUD_SLOT - 1;
+                    if held || border {
+                        self.framebuffer.point(slot_left + px, slot_top + py, color.rgb());
                     }
                 }
             }
-            
         }
     }
-    
 
-    
+    /// Cielo panorámico en la mitad de arriba. La imagen cubre los 360° a lo
+    /// ancho, así que cada columna toma la franja que corresponde al ángulo de
+    /// su rayo y al girar el cielo se desplaza sin cortes al pasar por 2π.
+    fn render_sky(&mut self, player: &Player) {
+        let sky = match &self.sky_texture {
+            Some(sky) => sky,
+            None => return,
+        };
 
-    fn render_floor(&mut self, player: &Player) {
+        let camera = Camera::from_player(player);
+        let width = self.framebuffer.width;
         let height = self.framebuffer.height;
-        let center = height / 2;
-    
-        // Pre-calcular algunas constantes
-        let floor_texture_width = self.floor_texture.width() as f64;
-        let floor_texture_height = self.floor_texture.height() as f64;
-    
-        for y in center..height {
-            // Calcular la distancia a este nivel del piso
-            let row_distance = (self.framebuffer.height as f64 / (2.0 * (y - center) as f64)) as f64;
-            
-            // Calcular las coordenadas del piso basadas en la dirección del jugador
-            let floor_step_x = row_distance * (player.direction + player.fov / 2.0).cos();
-            let floor_step_y = row_distance * (player.direction + player.fov / 2.0).sin();
-    
-            let mut floor_x = player.x + floor_step_x;
-            let mut floor_y = player.y + floor_step_y;
-    
-            for x in 0..self.framebuffer.width {
-                // Mapear las coordenadas del piso a las coordenadas de la textura
-                let texture_x = ((floor_x % 1.0) * floor_texture_width) as u32;
-                let texture_y = ((floor_y % 1.0) * floor_texture_height) as u32;
-    
-                // Obtener el color de la textura y renderizar el píxel en el framebuffer
-                let color = self.floor_texture.get_pixel(texture_x, texture_y).0;
-                let color = ((color[0] as u32) << 16) | ((color[1] as u32) << 8) | (color[2] as u32); // Convertir a formato RGB
-                self.framebuffer.point(x, y, color);
-    
-                // Actualizar las coordenadas del piso para el siguiente píxel
-                floor_x += floor_step_x;
-                floor_y += floor_step_y;
+        let center = height as f64 / 2.0;
+        let horizon = camera.horizon(height);
+        let sky_width = sky.width as f64;
+
+        for x in 0..width {
+            let camera_x = 2.0 * (x as f64) / (width as f64) - 1.0;
+            let (ray_x, ray_y) = camera.ray_direction(camera_x);
+            let angle = ray_y.atan2(ray_x).rem_euclid(std::f64::consts::TAU);
+            let texture_x = ((angle / std::f64::consts::TAU * sky_width) as u32).min(sky.width - 1);
+
+            // La imagen ocupa la media pantalla justo arriba del horizonte; más
+            // arriba se repite su primera fila
+            for y in 0..(horizon.ceil().max(0.0) as usize).min(height) {
+                let row = (y as f64 - (horizon - center)) / center * sky.height as f64;
+                let texture_y = (row.max(0.0) as u32).min(sky.height - 1);
+                self.framebuffer.point(x, y, sky.get(texture_x, texture_y) & 0xFFFFFF);
             }
         }
     }
-    
-
-    fn render_minimap(&mut self, map: &Map, player: &Player) {
-        // Dibujar el minimapa en la esquina superior izquierda
-        let minimap_scale = 4;
-
-        for y in 0..map.height {
-            for x in 0..map.width {
-                let color = if map.is_wall(x as f64, y as f64) {
-                    0xFFFFFF // Color de las paredes en el minimapa
-                } else {
-                    0x000000 // Color del suelo en el minimapa
-                };
-
-                for py in 0..minimap_scale {
-                    for px in 0..minimap_scale {
-                        self.framebuffer.point(x * minimap_scale + px, y * minimap_scale + py, color);
-                    }
+
+    /// Piso con perspectiva: cada fila debajo del horizonte está a una distancia
+    /// fija y se recorre de izquierda a derecha con los mismos rayos que las paredes.
+    fn render_floor(&mut self, map: &Map, player: &Player) {
+        let camera = Camera::from_player(player);
+        let width = self.framebuffer.width;
+        let height = self.framebuffer.height;
+        let horizon = camera.horizon(height);
+
+        let lit = !map.lighting.is_full_bright();
+
+        // Las filas empiezan justo debajo del horizonte, que está infinitamente lejos
+        let first_row = (horizon.floor() + 1.0).max(0.0) as usize;
+        for y in first_row..height {
+            // El piso está `eye_height` por debajo de los ojos
+            let row_distance = camera.eye_height * height as f64 / (y as f64 - horizon);
+            let ((mut floor_x, mut floor_y), (step_x, step_y)) = camera.floor_row(row_distance, width);
+
+            for x in 0..width {
+                let mut color = self.floor_texture.sample(floor_x, floor_y) & 0xFFFFFF;
+                if lit {
+                    color = lighting::modulate(color, map.lighting.at(floor_x, floor_y));
                 }
+                self.framebuffer.point(x, y, map.fog.apply(color, row_distance));
+
+                floor_x += step_x;
+                floor_y += step_y;
             }
         }
+    }
+
+    /// Techo de las celdas techadas, fila por fila como el piso; en el resto
+    /// queda el cielo.
+    fn render_ceiling(&mut self, map: &Map, player: &Player) {
+        let camera = Camera::from_player(player);
+        let width = self.framebuffer.width;
+        let height = self.framebuffer.height;
+        let horizon = camera.horizon(height);
+
+        let lit = !map.lighting.is_full_bright();
+
+        for y in 0..(horizon.ceil().max(0.0) as usize).min(height) {
+            // El techo está a una unidad del piso, `1 - eye_height` por encima de los ojos
+            let row_distance = (1.0 - camera.eye_height) * height as f64 / (horizon - y as f64);
+            let ((mut ceiling_x, mut ceiling_y), (step_x, step_y)) = camera.floor_row(row_distance, width);
+
+            for x in 0..width {
+                if map.has_ceiling(ceiling_x, ceiling_y) {
+                    let mut color = self.ceiling_texture.sample(ceiling_x, ceiling_y) & 0xFFFFFF;
+                    if lit {
+                        color = lighting::modulate(color, map.lighting.at(ceiling_x, ceiling_y));
+                    }
+                    self.framebuffer.point(x, y, map.fog.apply(color, row_distance));
+                }
 
-        // Dibujar la posición del jugador en el minimapa
-        let player_x = (player.x * minimap_scale as f64) as usize;
-        let player_y = (player.y * minimap_scale as f64) as usize;
-        for py in 0..minimap_scale {
-            for px in 0..minimap_scale {
-                self.framebuffer.point(player_x + px, player_y + py, 0xFF0000); // Rojo para la posición del jugador
+                ceiling_x += step_x;
+                ceiling_y += step_y;
             }
         }
     }
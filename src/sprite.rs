@@ -0,0 +1,112 @@
+use crate::camera::Camera;
+use crate::fog::Fog;
+use crate::framebuffer::Framebuffer;
+use crate::raycaster::Occluder;
+use crate::textures::TextureRegistry;
+
+/// Objeto plano que siempre mira a la cámara (billboard), como la meta, los
+/// objetos recogibles o los enemigos. `texture` es el glifo con el que se
+/// registró su textura.
+pub struct Sprite {
+    pub x: f64,
+    pub y: f64,
+    pub texture: char,
+}
+
+impl Sprite {
+    pub fn new(x: f64, y: f64, texture: char) -> Self {
+        Self { x, y, texture }
+    }
+}
+
+/// Dibuja los sprites de atrás hacia adelante, descartando los píxeles que
+/// quedan detrás de alguna de las paredes que `z_buffer` guarda por columna.
+pub fn render_sprites(
+    sprites: &[Sprite],
+    camera: &Camera,
+    framebuffer: &mut Framebuffer,
+    z_buffer: &[Vec<Occluder>],
+    textures: &TextureRegistry,
+    fog: &Fog,
+) {
+    let width = framebuffer.width as f64;
+    let height = framebuffer.height as f64;
+
+    // Ordenar de más lejano a más cercano para que los cercanos tapen a los lejanos
+    let mut order: Vec<(f64, &Sprite)> = sprites
+        .iter()
+        .map(|sprite| {
+            let dx = sprite.x - camera.x;
+            let dy = sprite.y - camera.y;
+            (dx * dx + dy * dy, sprite)
+        })
+        .collect();
+    order.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let inverse_det = 1.0 / (camera.plane_x * camera.dir_y - camera.dir_x * camera.plane_y);
+    let plane_length = (camera.plane_x * camera.plane_x + camera.plane_y * camera.plane_y).sqrt();
+
+    for (_, sprite) in order {
+        let texture = match textures.get(sprite.texture) {
+            Some(texture) => texture,
+            None => continue,
+        };
+
+        // Pasar la posición del sprite al espacio de la cámara
+        let dx = sprite.x - camera.x;
+        let dy = sprite.y - camera.y;
+        let transform_x = inverse_det * (camera.dir_y * dx - camera.dir_x * dy);
+        let depth = inverse_det * (-camera.plane_y * dx + camera.plane_x * dy);
+
+        // Detrás de la cámara o demasiado cerca
+        if depth <= 0.05 {
+            continue;
+        }
+
+        let screen_x = width / 2.0 * (1.0 + transform_x / depth);
+        // Misma escala que las paredes: una unidad de mundo mide height / depth píxeles de alto
+        let sprite_height = height / depth;
+        let sprite_width = width / 2.0 / plane_length / depth;
+
+        // El sprite va del piso a una unidad de altura; el horizonte está a la altura de los ojos
+        let top = camera.horizon(framebuffer.height) - (1.0 - camera.eye_height) * sprite_height;
+        let left = screen_x - sprite_width / 2.0;
+
+        let start_x = left.max(0.0) as usize;
+        let end_x = ((left + sprite_width).max(0.0) as usize).min(framebuffer.width);
+        let start_y = top.max(0.0) as usize;
+        let end_y = ((top + sprite_height).max(0.0) as usize).min(framebuffer.height);
+
+        let texture_width = texture.width;
+        let texture_height = texture.height;
+
+        for (x, occluders) in z_buffer.iter().enumerate().take(end_x).skip(start_x) {
+            // Solo las paredes más cercanas que el sprite lo tapan, desde su borde de arriba
+            let hidden_from = occluders
+                .iter()
+                .filter(|occluder| occluder.distance < depth)
+                .map(|occluder| occluder.top)
+                .min()
+                .unwrap_or(usize::MAX);
+            if hidden_from <= start_y {
+                continue;
+            }
+
+            let texture_x = ((x as f64 - left) / sprite_width * texture_width as f64) as u32;
+            let texture_x = texture_x.min(texture_width - 1);
+
+            for y in start_y..end_y.min(hidden_from) {
+                let texture_y = ((y as f64 - top) / sprite_height * texture_height as f64) as u32;
+                let texture_y = texture_y.min(texture_height - 1);
+                let color = texture.get(texture_x, texture_y);
+
+                // Píxeles transparentes
+                if color >> 24 < 128 {
+                    continue;
+                }
+
+                framebuffer.point(x, y, fog.apply(color & 0xFFFFFF, depth));
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageResult};
+
+/// Imagen ya decodificada a texeles `0xAARRGGBB`, lista para muestrear con
+/// aritmética de índices en los ciclos por píxel.
+///
+/// Una textura con mipmaps se reescala a potencias de dos y guarda versiones
+/// a la mitad, la cuarta parte, etc., para muestrear paredes lejanas sin
+/// parpadeo.
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    texels: Vec<u32>,
+    // Niveles 1, 2, ...; el nivel 0 es esta misma textura
+    mipmaps: Vec<Texture>,
+}
+
+impl Texture {
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let (width, height) = image.dimensions();
+        let texels = image
+            .to_rgba8()
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            texels,
+            mipmaps: Vec::new(),
+        }
+    }
+
+    /// Reescala a potencias de dos y genera la cadena de mipmaps promediando
+    /// bloques de 2×2 hasta llegar a 1×1.
+    pub fn from_image_mipmapped(image: &DynamicImage) -> Self {
+        let (width, height) = image.dimensions();
+        let (pow_width, pow_height) = (width.next_power_of_two(), height.next_power_of_two());
+        let mut texture = if (pow_width, pow_height) == (width, height) {
+            Self::from_image(image)
+        } else {
+            Self::from_image(&image.resize_exact(pow_width, pow_height, FilterType::Triangle))
+        };
+
+        let mut level = texture.half();
+        while let Some(next) = level {
+            level = next.half();
+            texture.mipmaps.push(next);
+        }
+        texture
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        Ok(Self::from_image(&image::open(path)?))
+    }
+
+    pub fn load_mipmapped(path: impl AsRef<Path>) -> ImageResult<Self> {
+        Ok(Self::from_image_mipmapped(&image::open(path)?))
+    }
+
+    /// Texel `0xAARRGGBB`; las coordenadas deben estar dentro de la textura.
+    pub fn get(&self, x: u32, y: u32) -> u32 {
+        self.texels[(y * self.width + x) as usize]
+    }
+
+    /// Texel en coordenadas de mundo que se repiten cada unidad, como en el
+    /// piso. Funciona también con coordenadas negativas.
+    pub fn sample(&self, u: f64, v: f64) -> u32 {
+        let x = ((u.rem_euclid(1.0) * self.width as f64) as u32).min(self.width - 1);
+        let y = ((v.rem_euclid(1.0) * self.height as f64) as u32).min(self.height - 1);
+        self.get(x, y)
+    }
+
+    /// Nivel de mipmap adecuado cuando a cada píxel de pantalla le tocan
+    /// `texels_per_pixel` texeles. Sin mipmaps es siempre la textura original.
+    pub fn mip(&self, texels_per_pixel: f64) -> &Texture {
+        if self.mipmaps.is_empty() || texels_per_pixel < 2.0 {
+            return self;
+        }
+
+        let level = (texels_per_pixel.log2().floor() as usize).min(self.mipmaps.len());
+        &self.mipmaps[level - 1]
+    }
+
+    /// La mitad de tamaño promediando bloques de 2×2, o `None` si ya es 1×1.
+    fn half(&self) -> Option<Texture> {
+        if self.width == 1 && self.height == 1 {
+            return None;
+        }
+
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let corners = [
+                    self.get((x * 2).min(self.width - 1), (y * 2).min(self.height - 1)),
+                    self.get((x * 2 + 1).min(self.width - 1), (y * 2).min(self.height - 1)),
+                    self.get((x * 2).min(self.width - 1), (y * 2 + 1).min(self.height - 1)),
+                    self.get((x * 2 + 1).min(self.width - 1), (y * 2 + 1).min(self.height - 1)),
+                ];
+                let channel = |shift: u32| {
+                    let sum: u32 = corners.iter().map(|texel| (texel >> shift) & 0xFF).sum();
+                    ((sum + 2) / 4) << shift
+                };
+                texels.push(channel(24) | channel(16) | channel(8) | channel(0));
+            }
+        }
+
+        Some(Texture {
+            width,
+            height,
+            texels,
+            mipmaps: Vec::new(),
+        })
+    }
+}
+
+/// Texturas indexadas por el glifo del mapa que las usa.
+pub struct TextureRegistry {
+    textures: HashMap<char, Texture>,
+    fallback: char,
+    mipmaps: bool,
+}
+
+impl TextureRegistry {
+    /// `fallback` es el glifo cuya textura se usa para glifos sin textura propia.
+    pub fn new(fallback: char) -> Self {
+        Self {
+            textures: HashMap::new(),
+            fallback,
+            mipmaps: false,
+        }
+    }
+
+    /// Igual que `new`, pero `load` genera mipmaps para cada textura.
+    pub fn mipmapped(fallback: char) -> Self {
+        Self {
+            mipmaps: true,
+            ..Self::new(fallback)
+        }
+    }
+
+    pub fn insert(&mut self, glyph: char, texture: Texture) {
+        self.textures.insert(glyph, texture);
+    }
+
+    pub fn load(&mut self, glyph: char, path: impl AsRef<Path>) -> ImageResult<()> {
+        let texture = if self.mipmaps {
+            Texture::load_mipmapped(path)?
+        } else {
+            Texture::load(path)?
+        };
+        self.insert(glyph, texture);
+        Ok(())
+    }
+
+    pub fn get(&self, glyph: char) -> Option<&Texture> {
+        self.textures
+            .get(&glyph)
+            .or_else(|| self.textures.get(&self.fallback))
+    }
+}
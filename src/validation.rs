@@ -0,0 +1,347 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::inventory::KeyColor;
+use crate::map::{is_wall_glyph, Map};
+
+/// Un problema encontrado por `Map::validate`. Las coordenadas son de celda y
+/// las filas se cuentan desde 0.
+#[derive(Debug, PartialEq)]
+pub enum ValidationIssue {
+    EmptyGrid,
+    RaggedRow { row: usize, expected: usize, found: usize },
+    OpenBorder { x: usize, y: usize },
+    StartOutOfBounds { x: f64, y: f64 },
+    StartInWall { x: usize, y: usize },
+    GoalOutOfBounds { x: f64, y: f64 },
+    GoalInWall { x: usize, y: usize },
+    MultipleGoalGlyphs { count: usize },
+    GoalMismatch { glyph: (usize, usize), goal: (usize, usize) },
+    GoalUnreachable,
+    MissingKey { color: KeyColor },
+    KeyUnreachable { color: KeyColor },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::EmptyGrid => write!(f, "el mapa no tiene celdas"),
+            ValidationIssue::RaggedRow { row, expected, found } => {
+                write!(f, "la fila {} tiene {} columnas pero se esperaban {}", row, found, expected)
+            }
+            ValidationIssue::OpenBorder { x, y } => {
+                write!(f, "el borde está abierto en la celda ({}, {})", x, y)
+            }
+            ValidationIssue::StartOutOfBounds { x, y } => {
+                write!(f, "la posición inicial ({}, {}) está fuera del mapa", x, y)
+            }
+            ValidationIssue::StartInWall { x, y } => {
+                write!(f, "la posición inicial cae en la pared ({}, {})", x, y)
+            }
+            ValidationIssue::GoalOutOfBounds { x, y } => {
+                write!(f, "la meta ({}, {}) está fuera del mapa", x, y)
+            }
+            ValidationIssue::GoalInWall { x, y } => write!(f, "la meta cae en la pared ({}, {})", x, y),
+            ValidationIssue::MultipleGoalGlyphs { count } => {
+                write!(f, "hay {} glifos 'E' pero debe haber una sola meta", count)
+            }
+            ValidationIssue::GoalMismatch { glyph, goal } => write!(
+                f,
+                "el glifo 'E' está en ({}, {}) pero la meta es ({}, {})",
+                glyph.0, glyph.1, goal.0, goal.1
+            ),
+            ValidationIssue::GoalUnreachable => {
+                write!(f, "la meta no se puede alcanzar desde la posición inicial")
+            }
+            ValidationIssue::MissingKey { color } => {
+                write!(f, "hay una puerta con llave {} pero ninguna llave de ese color", color.name())
+            }
+            ValidationIssue::KeyUnreachable { color } => {
+                write!(f, "la llave {} no se puede alcanzar y su puerta bloquea la meta", color.name())
+            }
+        }
+    }
+}
+
+/// Resultado de `Map::validate`: vacío si el mapa es jugable.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl Map {
+    /// Revisa que el mapa sea rectangular, esté cerrado, que el inicio y la meta
+    /// caigan en celdas transitables, que haya una sola meta, que cada puerta
+    /// con llave tenga su llave y que se pueda llegar a la meta desde el inicio
+    /// abriendo las puertas con llave solo después de recoger la llave.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let issues = &mut report.issues;
+
+        if self.grid.is_empty() || self.width == 0 {
+            issues.push(ValidationIssue::EmptyGrid);
+            return report;
+        }
+
+        for (row, cells) in self.grid.iter().enumerate() {
+            if cells.len() != self.width {
+                issues.push(ValidationIssue::RaggedRow { row, expected: self.width, found: cells.len() });
+            }
+        }
+        // El resto de las revisiones indexa la rejilla como rectángulo
+        if !issues.is_empty() {
+            return report;
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let on_border = x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height;
+                if on_border && !is_wall_glyph(self.grid[y][x]) {
+                    issues.push(ValidationIssue::OpenBorder { x, y });
+                }
+            }
+        }
+
+        let start = self.walkable_cell(self.start_x, self.start_y);
+        match start {
+            Err(CellProblem::OutOfBounds) => {
+                issues.push(ValidationIssue::StartOutOfBounds { x: self.start_x, y: self.start_y })
+            }
+            Err(CellProblem::Wall(x, y)) => issues.push(ValidationIssue::StartInWall { x, y }),
+            Ok(_) => {}
+        }
+
+        let goal = self.walkable_cell(self.end_x, self.end_y);
+        match goal {
+            Err(CellProblem::OutOfBounds) => {
+                issues.push(ValidationIssue::GoalOutOfBounds { x: self.end_x, y: self.end_y })
+            }
+            Err(CellProblem::Wall(x, y)) => issues.push(ValidationIssue::GoalInWall { x, y }),
+            Ok(_) => {}
+        }
+
+        // La meta puede venir solo de la cabecera; si hay glifos 'E' deben coincidir con ella
+        let goal_glyphs: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.grid[y][x] == 'E')
+            .collect();
+        if goal_glyphs.len() > 1 {
+            issues.push(ValidationIssue::MultipleGoalGlyphs { count: goal_glyphs.len() });
+        } else if let (Some(&glyph), Ok(goal)) = (goal_glyphs.first(), goal) {
+            if glyph != goal {
+                issues.push(ValidationIssue::GoalMismatch { glyph, goal });
+            }
+        }
+
+        for color in KeyColor::ALL {
+            let has_door = self.doors.values().any(|door| door.lock == Some(color));
+            let has_key = self.sprites.iter().any(|sprite| sprite.texture == color.key_glyph());
+            if has_door && !has_key {
+                issues.push(ValidationIssue::MissingKey { color });
+            }
+        }
+
+        if let (Ok(start), Ok(goal)) = (start, goal) {
+            let goal_index = goal.1 * self.width + goal.0;
+            let (reached, keys) = self.reachable_with_keys(start);
+            if !reached[goal_index] {
+                if !self.reachable_from(start, &KeyColor::ALL)[goal_index] {
+                    // Ni con todas las puertas abiertas se llega
+                    issues.push(ValidationIssue::GoalUnreachable);
+                } else {
+                    // Bloquean las puertas cuya llave está en el mapa pero no se alcanza;
+                    // las que no tienen llave ya quedaron como `MissingKey`
+                    for color in KeyColor::ALL {
+                        let locked_out = !keys.contains(&color)
+                            && self.doors.values().any(|door| door.lock == Some(color))
+                            && self.sprites.iter().any(|sprite| sprite.texture == color.key_glyph());
+                        if locked_out {
+                            issues.push(ValidationIssue::KeyUnreachable { color });
+                        }
+                    }
+                }
+            }
+        }
+
+        report
+    }
+
+    fn walkable_cell(&self, x: f64, y: f64) -> Result<(usize, usize), CellProblem> {
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return Err(CellProblem::OutOfBounds);
+        }
+
+        let (x, y) = (x.floor() as usize, y.floor() as usize);
+        if is_wall_glyph(self.grid[y][x]) {
+            return Err(CellProblem::Wall(x, y));
+        }
+        Ok((x, y))
+    }
+
+    /// Celdas alcanzables desde `start` recogiendo llaves por el camino: cada
+    /// llave alcanzada abre sus puertas y se vuelve a buscar hasta que no
+    /// aparecen llaves nuevas. Devuelve las celdas y las llaves recogidas.
+    fn reachable_with_keys(&self, start: (usize, usize)) -> (Vec<bool>, Vec<KeyColor>) {
+        let mut keys = Vec::new();
+        loop {
+            let reached = self.reachable_from(start, &keys);
+            let before = keys.len();
+            for sprite in &self.sprites {
+                let (x, y) = (sprite.x as usize, sprite.y as usize);
+                if x >= self.width || y >= self.height || !reached[y * self.width + x] {
+                    continue;
+                }
+                if let Some(color) = KeyColor::from_key_glyph(sprite.texture) {
+                    if !keys.contains(&color) {
+                        keys.push(color);
+                    }
+                }
+            }
+            if keys.len() == before {
+                return (reached, keys);
+            }
+        }
+    }
+
+    /// Celdas transitables alcanzables desde `start` moviéndose en cuatro
+    /// direcciones. Las puertas con llave solo se cruzan si su color está en `keys`.
+    fn reachable_from(&self, start: (usize, usize), keys: &[KeyColor]) -> Vec<bool> {
+        let mut reached = vec![false; self.width * self.height];
+        let mut queue = VecDeque::new();
+        reached[start.1 * self.width + start.0] = true;
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            let neighbors = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            for (nx, ny) in neighbors {
+                if nx >= self.width || ny >= self.height || is_wall_glyph(self.grid[ny][nx]) {
+                    continue;
+                }
+                let lock = self.doors.get(&(nx, ny)).and_then(|door| door.lock);
+                if lock.is_some_and(|color| !keys.contains(&color)) {
+                    continue;
+                }
+                let index = ny * self.width + nx;
+                if !reached[index] {
+                    reached[index] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        reached
+    }
+}
+
+#[derive(Clone, Copy)]
+enum CellProblem {
+    OutOfBounds,
+    Wall(usize, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Inicio en la celda (1, 1) y meta en la 'E' de la rejilla
+    fn map(rows: &[&str]) -> Map {
+        let grid: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        let goal = grid
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|&glyph| glyph == 'E').map(|x| (x as f64, y as f64)))
+            .unwrap();
+        Map::from_grid(String::from("prueba"), grid, (1.5, 1.5), 0.0, goal)
+    }
+
+    #[test]
+    fn ragged_grid_is_rejected() {
+        let map = map(&["#####", "#  E#", "###"]);
+        assert_eq!(map.validate().issues, vec![ValidationIssue::RaggedRow { row: 2, expected: 5, found: 3 }]);
+    }
+
+    #[test]
+    fn open_border_is_rejected() {
+        let map = map(&["# ###", "#  E#", "#####"]);
+        assert_eq!(map.validate().issues, vec![ValidationIssue::OpenBorder { x: 1, y: 0 }]);
+    }
+
+    #[test]
+    fn start_outside_the_map_is_rejected() {
+        let map = Map { start_x: -1.0, ..map(&["#####", "#  E#", "#####"]) };
+        assert_eq!(map.validate().issues, vec![ValidationIssue::StartOutOfBounds { x: -1.0, y: 1.5 }]);
+    }
+
+    #[test]
+    fn start_in_a_wall_is_rejected() {
+        let map = Map { start_x: 0.5, ..map(&["#####", "#  E#", "#####"]) };
+        assert_eq!(map.validate().issues, vec![ValidationIssue::StartInWall { x: 0, y: 1 }]);
+    }
+
+    #[test]
+    fn goal_in_a_wall_is_rejected() {
+        let map = Map { end_x: 4.0, ..map(&["#####", "#  E#", "#####"]) };
+        assert_eq!(map.validate().issues, vec![ValidationIssue::GoalInWall { x: 4, y: 1 }]);
+    }
+
+    #[test]
+    fn goal_glyph_must_match_goal() {
+        let map = Map { end_x: 2.0, ..map(&["#####", "#  E#", "#####"]) };
+        assert_eq!(map.validate().issues, vec![ValidationIssue::GoalMismatch { glyph: (3, 1), goal: (2, 1) }]);
+    }
+
+    #[test]
+    fn several_goal_glyphs_are_rejected() {
+        let map = map(&["#####", "# EE#", "#####"]);
+        assert_eq!(map.validate().issues, vec![ValidationIssue::MultipleGoalGlyphs { count: 2 }]);
+    }
+
+    #[test]
+    fn key_before_its_door_is_valid() {
+        let map = map(&["#######", "#r R E#", "#######"]);
+        assert!(map.validate().is_ok(), "{}", map.validate());
+    }
+
+    #[test]
+    fn key_behind_its_own_door_is_rejected() {
+        let map = map(&["#######", "# R rE#", "#######"]);
+        assert_eq!(map.validate().issues, vec![ValidationIssue::KeyUnreachable { color: KeyColor::Red }]);
+    }
+
+    #[test]
+    fn goal_behind_door_without_key_is_rejected() {
+        let map = map(&["######", "#  UE#", "######"]);
+        assert_eq!(map.validate().issues, vec![ValidationIssue::MissingKey { color: KeyColor::Blue }]);
+    }
+
+    #[test]
+    fn keys_open_doors_in_sequence() {
+        // La llave amarilla está detrás de la puerta roja
+        let map = map(&["##########", "#r R y YE#", "##########"]);
+        assert!(map.validate().is_ok(), "{}", map.validate());
+    }
+
+    #[test]
+    fn walled_off_goal_is_unreachable() {
+        let map = map(&["#######", "# r #E#", "#######"]);
+        assert_eq!(map.validate().issues, vec![ValidationIssue::GoalUnreachable]);
+    }
+}